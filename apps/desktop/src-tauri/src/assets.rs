@@ -0,0 +1,628 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tauri::State;
+use walkdir::WalkDir;
+
+use crate::{
+    error::{VaultError, VaultResult},
+    journal::{self, Step},
+    state::{OpenVault, VaultState},
+    templates::TEMPLATES_DIR,
+    thumbs,
+    trash::{self, TrashEntry, TrashKind},
+    vault::{content_hash, path_to_forward_slashes, write_atomic},
+};
+
+pub(crate) const ASSETS_DIR: &str = "assets";
+const ASSETS_FILE: &str = ".homebase/assets.json";
+/// Hex digits of the content hash used in file names. A longer prefix is only
+/// needed if two different files ever share this one.
+const SHORT_HASH_LEN: usize = 16;
+/// Unreferenced assets younger than this are left alone by garbage
+/// collection: the note linking to a fresh import may not be saved yet.
+const DEFAULT_GRACE_DAYS: u32 = 7;
+
+/// What was recorded about a file when it was first imported.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentMeta {
+    pub mime_type: String,
+    pub size: u64,
+    pub original_name: Option<String>,
+    pub imported_at: String,
+}
+
+/// Keyed by file name within `assets/`.
+pub(crate) fn load_meta(vault_root: &Path) -> VaultResult<BTreeMap<String, AttachmentMeta>> {
+    let path = vault_root.join(ASSETS_FILE);
+    match fs::read_to_string(&path) {
+        Ok(raw) => serde_json::from_str(&raw).map_err(|e| VaultError::invalid_data(&path, e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(VaultError::io(&path, e)),
+    }
+}
+
+pub(crate) fn save_meta(
+    vault_root: &Path,
+    meta: &BTreeMap<String, AttachmentMeta>,
+) -> VaultResult<()> {
+    let raw = serde_json::to_string_pretty(meta).map_err(VaultError::internal)?;
+    write_atomic(&vault_root.join(ASSETS_FILE), raw)
+}
+
+/// Lowercased extension of `name`, if it looks like one.
+fn extension(name: &str) -> Option<String> {
+    let (_, ext) = name.rsplit_once('.')?;
+    let valid =
+        !ext.is_empty() && ext.len() <= 10 && ext.chars().all(|c| c.is_ascii_alphanumeric());
+    valid.then(|| ext.to_ascii_lowercase())
+}
+
+/// Picks `assets/<hash>.<ext>` for `bytes`. Returns the file name and whether
+/// an identical file is already stored under it.
+fn asset_file_name(assets: &Path, bytes: &[u8], ext: Option<&str>) -> VaultResult<(String, bool)> {
+    let hash = content_hash(bytes);
+    let name = |hash: &str| match ext {
+        Some(ext) => format!("{}.{}", hash, ext),
+        None => hash.to_string(),
+    };
+    let short = name(&hash[..SHORT_HASH_LEN]);
+    match fs::read(assets.join(&short)) {
+        Ok(existing) if existing == bytes => Ok((short, true)),
+        Ok(_) => {
+            let full = name(&hash);
+            Ok((full.clone(), assets.join(&full).is_file()))
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok((short, false)),
+        Err(e) => Err(VaultError::io(assets.join(&short), e)),
+    }
+}
+
+fn escape_label(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportAttachmentArgs {
+    /// A file on disk to copy in. Exactly one of this and `bytes` is required.
+    pub source_path: Option<String>,
+    pub bytes: Option<Vec<u8>>,
+    /// Name to take the extension and link text from when importing `bytes`,
+    /// e.g. `screenshot.png`.
+    pub file_name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportAttachmentResult {
+    pub relative_path: String,
+    /// `![name](/assets/…)` for images, `[name](…)` for anything else.
+    pub markdown: String,
+    /// True when an identical file was already in `assets/`.
+    pub deduplicated: bool,
+    #[serde(flatten)]
+    pub meta: AttachmentMeta,
+}
+
+/// Stores a file under `assets/`, named by its content hash so the same file
+/// imported twice is only kept once.
+#[tauri::command]
+pub fn vault_import_attachment(
+    state: State<'_, VaultState>,
+    args: ImportAttachmentArgs,
+) -> VaultResult<ImportAttachmentResult> {
+    state.with_writable_vault(|vault| {
+        let (bytes, original_name) = match (&args.source_path, args.bytes) {
+            (Some(source), None) => {
+                let source = PathBuf::from(source);
+                if !source.is_file() {
+                    return Err(VaultError::invalid_input("sourcePath", "is not a file"));
+                }
+                let bytes = fs::read(&source).map_err(|e| VaultError::io(&source, e))?;
+                let name = source
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned());
+                (bytes, args.file_name.or(name))
+            }
+            (None, Some(bytes)) => (bytes, args.file_name),
+            _ => {
+                return Err(VaultError::invalid_input(
+                    "sourcePath",
+                    "exactly one of sourcePath and bytes is required",
+                ))
+            }
+        };
+        let ext = original_name.as_deref().and_then(extension);
+        let assets = vault.root.join(ASSETS_DIR);
+        let (file_name, deduplicated) = asset_file_name(&assets, &bytes, ext.as_deref())?;
+        let asset_path = assets.join(&file_name);
+        if deduplicated {
+            // Restarts the garbage-collection grace period, which runs from
+            // the file's mtime.
+            fs::File::options()
+                .write(true)
+                .open(&asset_path)
+                .and_then(|file| file.set_modified(SystemTime::now()))
+                .map_err(|e| VaultError::io(&asset_path, e))?;
+        } else {
+            write_atomic(&asset_path, &bytes)?;
+        }
+
+        let mut all_meta = load_meta(&vault.root)?;
+        let meta = all_meta
+            .entry(file_name.clone())
+            .or_insert_with(|| AttachmentMeta {
+                mime_type: mime_guess::from_path(&file_name)
+                    .first_or_octet_stream()
+                    .essence_str()
+                    .to_string(),
+                size: bytes.len() as u64,
+                original_name: original_name.clone(),
+                imported_at: Utc::now().to_rfc3339(),
+            })
+            .clone();
+        save_meta(&vault.root, &all_meta)?;
+
+        let relative_path = format!("{}/{}", ASSETS_DIR, file_name);
+        let label = original_name
+            .as_deref()
+            .map(|name| name.rsplit_once('.').map_or(name, |(stem, _)| stem))
+            .filter(|label| !label.is_empty())
+            .unwrap_or(&file_name);
+        let markdown = format!(
+            "{}[{}](/{})",
+            if meta.mime_type.starts_with("image/") {
+                "!"
+            } else {
+                ""
+            },
+            escape_label(label),
+            relative_path
+        );
+        Ok(ImportAttachmentResult {
+            relative_path,
+            markdown,
+            deduplicated,
+            meta,
+        })
+    })
+}
+
+/// Link destinations in a note's markdown: inline links and images, reference
+/// definitions and HTML `src` attributes, skipping fenced code blocks.
+fn link_targets(contents: &str) -> Vec<&str> {
+    let mut targets = Vec::new();
+    let mut in_fence = false;
+    for line in contents.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        // `[label]: target "title"`
+        if let Some(rest) = trimmed.strip_prefix('[') {
+            if let Some((_, def)) = rest.split_once("]:") {
+                if let Some(target) = destination(def.trim_start(), false) {
+                    targets.push(target);
+                }
+            }
+        }
+        let mut rest = line;
+        while let Some(at) = rest.find("](") {
+            rest = &rest[at + 2..];
+            if let Some(target) = destination(rest.trim_start(), true) {
+                targets.push(target);
+            }
+        }
+        let mut rest = line;
+        while let Some(at) = rest.find("src=") {
+            rest = &rest[at + 4..];
+            let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+                continue;
+            };
+            if let Some(end) = rest[1..].find(quote) {
+                targets.push(&rest[1..1 + end]);
+            }
+        }
+    }
+    targets
+}
+
+/// The destination at the start of `s`: `<...>`, or up to whitespace (and, in
+/// an inline link, the closing parenthesis, allowing balanced pairs inside).
+fn destination(s: &str, inline: bool) -> Option<&str> {
+    if let Some(rest) = s.strip_prefix('<') {
+        return rest.find('>').map(|end| &rest[..end]);
+    }
+    let mut depth = 0usize;
+    let end = s
+        .char_indices()
+        .find(|&(_, c)| match c {
+            '(' if inline => {
+                depth += 1;
+                false
+            }
+            ')' if inline => {
+                if depth == 0 {
+                    return true;
+                }
+                depth -= 1;
+                false
+            }
+            c => c.is_whitespace(),
+        })
+        .map_or(s.len(), |(i, _)| i);
+    (end > 0).then(|| &s[..end])
+}
+
+pub(crate) fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%')
+            .then(|| s.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match hex {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Resolves a link in the note at `note_rel` to a vault-relative path.
+/// `None` for external URLs, pure anchors and paths that leave the vault.
+///
+/// Import used to write `../assets/…` links relative to the note, which stop
+/// resolving once the note moves to another depth. A relative link that
+/// misses `assets/` but names `assets/…` after its leading `..` segments is
+/// taken to mean the vault's `assets/`.
+fn resolve_link(note_rel: &str, target: &str) -> Option<String> {
+    let target = target.split(['#', '?']).next().unwrap_or_default();
+    let has_scheme = target
+        .split_once(':')
+        .is_some_and(|(scheme, _)| !scheme.is_empty() && !scheme.contains('/'));
+    if target.is_empty() || has_scheme {
+        return None;
+    }
+    let target = percent_decode(target);
+    let (base, target) = match target.strip_prefix('/') {
+        Some(from_root) => ("", from_root),
+        None => (
+            note_rel.rsplit_once('/').map_or("", |(dir, _)| dir),
+            &*target,
+        ),
+    };
+    let mut parts: Vec<&str> = base.split('/').filter(|p| !p.is_empty()).collect();
+    let mut escaped = false;
+    for part in target.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => escaped |= parts.pop().is_none(),
+            part => parts.push(part),
+        }
+    }
+    let resolved = parts.join("/");
+    if !escaped && resolved.starts_with("assets/") {
+        return Some(resolved);
+    }
+    let unanchored: Vec<&str> = target
+        .split(['/', '\\'])
+        .skip_while(|part| matches!(*part, "" | "." | ".."))
+        .collect();
+    if unanchored.first() == Some(&ASSETS_DIR) {
+        return Some(unanchored.join("/"));
+    }
+    (!escaped).then_some(resolved)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanAsset {
+    pub relative_path: String,
+    pub size: u64,
+    pub modified: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrokenAssetLink {
+    pub note_path: String,
+    /// The link as written in the note.
+    pub target: String,
+    /// Where it points, vault-relative.
+    pub resolved_path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetScan {
+    /// Files in `assets/` that no note links to, oldest first.
+    pub orphans: Vec<OrphanAsset>,
+    /// Links into `assets/` whose file does not exist.
+    pub broken_links: Vec<BrokenAssetLink>,
+    pub referenced_count: usize,
+}
+
+/// Cross-references every note, archived ones included, and every template
+/// against `assets/`. Notes in the trash do not count; restoring one may leave
+/// broken links.
+fn scan_assets(vault: &OpenVault) -> AssetScan {
+    let notes = vault
+        .index
+        .list_notes(true)
+        .into_iter()
+        .map(|e| e.relative_path);
+    scan_sources(&vault.root, notes)
+}
+
+/// Cross-references `notes` and every template against `assets/`.
+fn scan_sources(vault_root: &Path, notes: impl Iterator<Item = String>) -> AssetScan {
+    let templates = fs::read_dir(vault_root.join(TEMPLATES_DIR))
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_str()?.to_string();
+            name.ends_with(".md")
+                .then(|| format!("{}/{}", TEMPLATES_DIR, name))
+        });
+
+    let mut referenced = HashSet::new();
+    let mut broken_links = Vec::new();
+    for source in notes.chain(templates) {
+        let Ok(contents) = fs::read_to_string(vault_root.join(&source)) else {
+            continue;
+        };
+        for target in link_targets(&contents) {
+            let Some(resolved) = resolve_link(&source, target) else {
+                continue;
+            };
+            if !resolved.starts_with("assets/") {
+                continue;
+            }
+            if !vault_root.join(&resolved).is_file() {
+                broken_links.push(BrokenAssetLink {
+                    note_path: source.clone(),
+                    target: target.to_string(),
+                    resolved_path: resolved.clone(),
+                });
+            }
+            referenced.insert(resolved);
+        }
+    }
+    broken_links.sort_by(|a, b| a.note_path.cmp(&b.note_path));
+
+    let mut orphans: Vec<OrphanAsset> = WalkDir::new(vault_root.join(ASSETS_DIR))
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let rel = path_to_forward_slashes(e.path().strip_prefix(vault_root).ok()?);
+            if referenced.contains(&rel) {
+                return None;
+            }
+            let metadata = e.metadata().ok()?;
+            let modified: DateTime<Utc> = metadata.modified().ok()?.into();
+            Some(OrphanAsset {
+                relative_path: rel,
+                size: metadata.len(),
+                modified: modified.to_rfc3339(),
+            })
+        })
+        .collect();
+    orphans.sort_by(|a, b| a.modified.cmp(&b.modified));
+
+    AssetScan {
+        orphans,
+        broken_links,
+        referenced_count: referenced.len(),
+    }
+}
+
+/// Drops the import records of trashed assets from `.homebase/assets.json`,
+/// recording the change so undoing the clean-up brings them back.
+fn forget_meta(vault: &mut OpenVault, trashed: &[TrashEntry]) -> VaultResult<()> {
+    let mut meta = load_meta(&vault.root)?;
+    let before_len = meta.len();
+    for entry in trashed {
+        if let Some(name) = entry.original_path.strip_prefix("assets/") {
+            meta.remove(name);
+        }
+    }
+    if meta.len() == before_len {
+        return Ok(());
+    }
+    let path = vault.root.join(ASSETS_FILE);
+    let before = fs::read_to_string(&path).map_err(|e| VaultError::io(&path, e))?;
+    let after = serde_json::to_string_pretty(&meta).map_err(VaultError::internal)?;
+    write_atomic(&path, &after)?;
+    vault.record_step(Step::Write {
+        path: ASSETS_FILE.to_string(),
+        before,
+        after,
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub fn vault_scan_assets(state: State<'_, VaultState>) -> VaultResult<AssetScan> {
+    state.with_vault(|vault| {
+        vault.sync_index()?;
+        Ok(scan_assets(vault))
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcAssetsResult {
+    pub trashed: Vec<TrashEntry>,
+    /// Orphans left in place because they are still within the grace period.
+    pub pending: Vec<OrphanAsset>,
+}
+
+/// Moves unreferenced assets older than `grace_days` (default 7) to the
+/// trash, as one undoable step.
+#[tauri::command]
+pub fn vault_gc_assets(
+    state: State<'_, VaultState>,
+    grace_days: Option<u32>,
+) -> VaultResult<GcAssetsResult> {
+    state.with_writable_vault(|vault| {
+        let grace = Duration::days(i64::from(grace_days.unwrap_or(DEFAULT_GRACE_DAYS)));
+        let cutoff = Utc::now() - grace;
+        let mut trashed = Vec::new();
+        let mut pending = Vec::new();
+        let mut result = Ok(());
+        vault.sync_index()?;
+        for orphan in scan_assets(vault).orphans {
+            let expired = DateTime::parse_from_rfc3339(&orphan.modified)
+                .is_ok_and(|modified| modified < cutoff);
+            if !expired {
+                pending.push(orphan);
+                continue;
+            }
+            match trash::move_to_trash(vault, &orphan.relative_path, TrashKind::Asset) {
+                Ok(entry) => {
+                    vault.record_step(Step::Trash {
+                        path: orphan.relative_path,
+                        entry_id: entry.id.clone(),
+                    });
+                    trashed.push(entry);
+                }
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+        if let Err(e) = forget_meta(vault, &trashed) {
+            result = result.and(Err(e));
+        }
+        // What was trashed before a failure stays undoable.
+        journal::commit(vault, "Clean up attachments");
+        thumbs::prune_thumbnails(&vault.root);
+        result.map(|_| GcAssetsResult { trashed, pending })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_relative_to_the_note() {
+        let note = "notes/inbox/a.md";
+        assert_eq!(
+            resolve_link(note, "../../assets/x.png").as_deref(),
+            Some("assets/x.png")
+        );
+        assert_eq!(
+            resolve_link(note, "./img/y.png").as_deref(),
+            Some("notes/inbox/img/y.png")
+        );
+        assert_eq!(
+            resolve_link(note, "/assets/x.png#frag").as_deref(),
+            Some("assets/x.png")
+        );
+        assert_eq!(
+            resolve_link(note, "../../assets/my%20file.png?v=2").as_deref(),
+            Some("assets/my file.png")
+        );
+    }
+
+    #[test]
+    fn skips_urls_anchors_and_escapes() {
+        let note = "notes/inbox/a.md";
+        assert_eq!(resolve_link(note, "https://example.com/x.png"), None);
+        assert_eq!(resolve_link(note, "mailto:me@example.com"), None);
+        assert_eq!(resolve_link(note, "#heading"), None);
+        assert_eq!(resolve_link(note, "../../../outside.png"), None);
+    }
+
+    #[test]
+    fn relative_asset_links_survive_moving_the_note() {
+        let archived = "notes/archive/inbox/a.md";
+        assert_eq!(
+            resolve_link(archived, "../../assets/x.png").as_deref(),
+            Some("assets/x.png")
+        );
+        assert_eq!(
+            resolve_link("notes/a.md", "../../assets/x.png").as_deref(),
+            Some("assets/x.png")
+        );
+        assert_eq!(
+            resolve_link(archived, "../img/y.png").as_deref(),
+            Some("notes/archive/img/y.png")
+        );
+    }
+
+    #[test]
+    fn notes_archived_after_import_keep_their_assets() {
+        let root = std::env::temp_dir().join(format!("homebase-assets-{}", std::process::id()));
+        let archived = "notes/archive/inbox/a.md";
+        fs::create_dir_all(root.join("notes/archive/inbox")).unwrap();
+        fs::create_dir_all(root.join(ASSETS_DIR)).unwrap();
+        for name in ["old.png", "new.png", "unused.png"] {
+            fs::write(root.join(ASSETS_DIR).join(name), "png").unwrap();
+        }
+        // Imported while the note was still at notes/inbox/a.md.
+        fs::write(
+            root.join(archived),
+            "![old](../../assets/old.png)\n![new](/assets/new.png)\n",
+        )
+        .unwrap();
+
+        let scan = scan_sources(&root, [archived.to_string()].into_iter());
+        let orphans: Vec<&str> = scan
+            .orphans
+            .iter()
+            .map(|o| o.relative_path.as_str())
+            .collect();
+        assert_eq!(orphans, ["assets/unused.png"]);
+        assert!(scan.broken_links.is_empty());
+        assert_eq!(scan.referenced_count, 2);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn finds_inline_reference_and_html_links_outside_fences() {
+        let contents = "![a](../../assets/a.png \"title\")\n\
+                        [ref]: <../../assets/b c.pdf>\n\
+                        <img src=\"../../assets/c.png\">\n\
+                        ```\n![skip](../../assets/d.png)\n```\n\
+                        [nested](../../assets/e_(1).png)\n";
+        assert_eq!(
+            link_targets(contents),
+            [
+                "../../assets/a.png",
+                "../../assets/b c.pdf",
+                "../../assets/c.png",
+                "../../assets/e_(1).png"
+            ]
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tauri::State;
+
+use crate::{
+    error::{VaultError, VaultResult},
+    journal,
+    moves::CollisionPolicy,
+    state::{OpenVault, VaultState},
+    trash,
+    vault::{
+        archive_note, archive_target, move_note, move_target, path_to_forward_slashes,
+        read_parsed_note, update_note_meta_recorded, validate_relative_path,
+    },
+};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum BatchOp {
+    Move {
+        relative_path: String,
+        target_dir: String,
+    },
+    Archive {
+        relative_path: String,
+    },
+    /// Adds and removes entries in the note's `topics`.
+    Tag {
+        relative_path: String,
+        #[serde(default)]
+        add: Vec<String>,
+        #[serde(default)]
+        remove: Vec<String>,
+    },
+    /// Moves the note to the trash, so it can be restored.
+    Delete {
+        relative_path: String,
+    },
+}
+
+impl BatchOp {
+    fn relative_path(&self) -> &str {
+        match self {
+            BatchOp::Move { relative_path, .. }
+            | BatchOp::Archive { relative_path }
+            | BatchOp::Tag { relative_path, .. }
+            | BatchOp::Delete { relative_path } => relative_path,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BatchItemStatus {
+    /// Done, and part of a committed batch.
+    Applied,
+    /// Rejected during validation; nothing in the batch was applied.
+    Invalid,
+    /// Valid, but not attempted because another item was invalid or failed.
+    Skipped,
+    /// Failed while being applied; every earlier item was rolled back.
+    Failed,
+    /// Applied, then undone because a later item failed.
+    RolledBack,
+    /// Applied, and rolling back did not put the note back where it was;
+    /// `relativePath` is where it was left.
+    RollbackFailed,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItemResult {
+    pub status: BatchItemStatus,
+    /// Where the note is now.
+    pub relative_path: String,
+    pub error: Option<VaultError>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchResult {
+    pub committed: bool,
+    pub items: Vec<BatchItemResult>,
+}
+
+/// Checks one operation without touching the disk and returns the note's path
+/// and where it will end up (`None` for deletes).
+fn plan(vault: &OpenVault, op: &BatchOp) -> VaultResult<(String, Option<String>)> {
+    match op {
+        BatchOp::Move {
+            relative_path,
+            target_dir,
+        } => {
+            let (src, dest) = move_target(vault, relative_path, target_dir)?;
+            Ok((src, Some(dest)))
+        }
+        BatchOp::Archive { relative_path } => {
+            let (src, dest) = archive_target(vault, relative_path)?;
+            Ok((src, Some(dest)))
+        }
+        BatchOp::Tag {
+            relative_path,
+            add,
+            remove,
+        } => {
+            // Also rejects notes whose frontmatter does not parse, which
+            // could not be tagged without rewriting them.
+            let (rel, _) = read_parsed_note(vault, relative_path)?;
+            if add.iter().chain(remove).any(|t| t.trim().is_empty()) {
+                return Err(VaultError::invalid_input("topics", "cannot be empty"));
+            }
+            Ok((rel.clone(), Some(rel)))
+        }
+        BatchOp::Delete { relative_path } => {
+            Ok((trash::trash_note_source(vault, relative_path)?, None))
+        }
+    }
+}
+
+fn validate(vault: &OpenVault, ops: &[BatchOp]) -> Vec<VaultResult<()>> {
+    let mut sources = HashSet::new();
+    let mut claimed = HashSet::new();
+    ops.iter()
+        .map(|op| {
+            let (src, dest) = plan(vault, op)?;
+            if !sources.insert(src.clone()) {
+                return Err(VaultError::invalid_input(
+                    "ops",
+                    format!("{} appears more than once in the batch", src),
+                ));
+            }
+            if let Some(dest) = dest.filter(|dest| *dest != src) {
+                // Batches never overwrite or rename around existing notes.
+                if vault.root.join(&dest).exists() || !claimed.insert(dest.clone()) {
+                    return Err(VaultError::already_exists(&dest));
+                }
+            }
+            Ok(())
+        })
+        .collect()
+}
+
+/// Applies one operation, recording its steps so a later failure can roll
+/// the whole batch back. Returns where the note is now.
+fn apply(vault: &mut OpenVault, op: &BatchOp) -> VaultResult<String> {
+    let rel = path_to_forward_slashes(&validate_relative_path(op.relative_path())?);
+    match op {
+        BatchOp::Move { target_dir, .. } => {
+            move_note(vault, &rel, target_dir, CollisionPolicy::Fail)
+        }
+        BatchOp::Archive { .. } => archive_note(vault, &rel, CollisionPolicy::Fail),
+        BatchOp::Tag { add, remove, .. } => {
+            update_note_meta_recorded(vault, &rel, |meta| {
+                meta.topics.retain(|t| !remove.contains(t));
+                for topic in add {
+                    let topic = topic.trim().to_string();
+                    if !meta.topics.contains(&topic) {
+                        meta.topics.push(topic);
+                    }
+                }
+            })?;
+            vault.index.refresh_note(&vault.root, &rel);
+            Ok(rel)
+        }
+        BatchOp::Delete { .. } => {
+            trash::trash_note(vault, &rel)?;
+            Ok(rel)
+        }
+    }
+}
+
+/// Applies `ops` in order as one unit: everything is validated before the
+/// first change, and a failure part-way undoes the items already applied.
+#[tauri::command]
+pub fn vault_batch(state: State<'_, VaultState>, ops: Vec<BatchOp>) -> VaultResult<BatchResult> {
+    state.with_writable_vault(|vault| {
+        let checks = validate(vault, &ops);
+        if checks.iter().any(|c| c.is_err()) {
+            let items = ops
+                .iter()
+                .zip(checks)
+                .map(|(op, check)| BatchItemResult {
+                    status: if check.is_ok() {
+                        BatchItemStatus::Skipped
+                    } else {
+                        BatchItemStatus::Invalid
+                    },
+                    relative_path: op.relative_path().to_string(),
+                    error: check.err(),
+                })
+                .collect();
+            return Ok(BatchResult {
+                committed: false,
+                items,
+            });
+        }
+
+        let mut items: Vec<BatchItemResult> = Vec::with_capacity(ops.len());
+        let mut failed = false;
+        for op in &ops {
+            if failed {
+                items.push(BatchItemResult {
+                    status: BatchItemStatus::Skipped,
+                    relative_path: op.relative_path().to_string(),
+                    error: None,
+                });
+                continue;
+            }
+            match apply(vault, op) {
+                Ok(relative_path) => {
+                    items.push(BatchItemResult {
+                        status: BatchItemStatus::Applied,
+                        relative_path,
+                        error: None,
+                    });
+                }
+                Err(e) => {
+                    failed = true;
+                    items.push(BatchItemResult {
+                        status: BatchItemStatus::Failed,
+                        relative_path: op.relative_path().to_string(),
+                        error: Some(e),
+                    });
+                }
+            }
+        }
+
+        if !failed {
+            journal::commit(vault, "Batch");
+        } else {
+            journal::roll_back(vault);
+            for (op, item) in ops.iter().zip(&mut items) {
+                if item.status != BatchItemStatus::Applied {
+                    continue;
+                }
+                let original =
+                    path_to_forward_slashes(&validate_relative_path(op.relative_path())?);
+                if vault.root.join(&original).is_file() {
+                    item.status = BatchItemStatus::RolledBack;
+                    item.relative_path = original;
+                } else {
+                    item.status = BatchItemStatus::RollbackFailed;
+                }
+            }
+        }
+
+        Ok(BatchResult {
+            committed: !failed,
+            items,
+        })
+    })
+}
@@ -0,0 +1,121 @@
+use serde::Serialize;
+use std::{io, path::Path};
+
+use crate::lock::LockInfo;
+
+/// Error returned by every vault command. Serialized as `{ "code": "...", ...context }`
+/// so the frontend can branch on `code` instead of matching English messages.
+#[derive(Debug, thiserror::Error, Serialize)]
+#[serde(tag = "code", rename_all_fields = "camelCase")]
+pub enum VaultError {
+    #[error("{path} does not exist")]
+    NotFound { path: String },
+
+    #[error("No {kind} with id {id}")]
+    IdNotFound { kind: &'static str, id: String },
+
+    #[error("{path} already exists")]
+    AlreadyExists { path: String },
+
+    #[error("Invalid path {path}: {reason}")]
+    InvalidPath { path: String, reason: String },
+
+    #[error("{path} is outside the vault")]
+    OutsideVault { path: String },
+
+    #[error("{path} is not empty")]
+    NotEmpty { path: String },
+
+    #[error("Invalid {field}: {reason}")]
+    InvalidInput { field: String, reason: String },
+
+    #[error("{path} contains invalid data: {message}")]
+    InvalidData { path: String, message: String },
+
+    #[error("I/O error on {path}: {message}")]
+    Io { path: String, message: String },
+
+    /// `current_contents` is what is on disk now (`None` if the file is gone), so
+    /// the UI can offer a merge instead of a blind retry.
+    #[error("{path} was changed since it was loaded")]
+    Conflict {
+        path: String,
+        current_contents: Option<String>,
+    },
+
+    #[error("Vault is read-only: it is open in another Homebase process (pid {})", holder.pid)]
+    ReadOnly { holder: LockInfo },
+
+    #[error("Vault was locked by another process while opening it")]
+    LockContended,
+
+    #[error("Vault format version {found} is newer than the supported version {supported}")]
+    UnsupportedVersion { found: u32, supported: u32 },
+
+    #[error("Vault folder {path} is not available")]
+    VaultUnavailable { path: String },
+
+    #[error("{message}")]
+    Internal { message: String },
+}
+
+pub type VaultResult<T> = Result<T, VaultError>;
+
+fn display_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+impl VaultError {
+    /// Maps an I/O failure on `path` to the most specific error code.
+    pub(crate) fn io(path: impl AsRef<Path>, err: io::Error) -> Self {
+        let path = display_path(path.as_ref());
+        match err.kind() {
+            io::ErrorKind::NotFound => VaultError::NotFound { path },
+            io::ErrorKind::AlreadyExists => VaultError::AlreadyExists { path },
+            io::ErrorKind::DirectoryNotEmpty => VaultError::NotEmpty { path },
+            _ => VaultError::Io {
+                path,
+                message: err.to_string(),
+            },
+        }
+    }
+
+    pub(crate) fn not_found(path: impl AsRef<Path>) -> Self {
+        VaultError::NotFound {
+            path: display_path(path.as_ref()),
+        }
+    }
+
+    pub(crate) fn already_exists(path: impl AsRef<Path>) -> Self {
+        VaultError::AlreadyExists {
+            path: display_path(path.as_ref()),
+        }
+    }
+
+    pub(crate) fn invalid_path(path: impl AsRef<Path>, reason: impl Into<String>) -> Self {
+        VaultError::InvalidPath {
+            path: display_path(path.as_ref()),
+            reason: reason.into(),
+        }
+    }
+
+    pub(crate) fn invalid_input(field: &str, reason: impl Into<String>) -> Self {
+        VaultError::InvalidInput {
+            field: field.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    pub(crate) fn invalid_data(path: impl AsRef<Path>, message: impl ToString) -> Self {
+        VaultError::InvalidData {
+            path: display_path(path.as_ref()),
+            message: message.to_string(),
+        }
+    }
+
+    pub(crate) fn internal(message: impl ToString) -> Self {
+        VaultError::Internal {
+            message: message.to_string(),
+        }
+    }
+}
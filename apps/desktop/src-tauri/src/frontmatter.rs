@@ -0,0 +1,219 @@
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use serde_yaml::{Mapping, Value};
+
+/// Reads a scalar as text, so hand-written values like `id: 123` are not a
+/// parse error. `None` for null and for values that are not scalars.
+fn scalar_text(value: Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Tagged(tagged) => scalar_text(tagged.value),
+        Value::Null | Value::Sequence(_) | Value::Mapping(_) => None,
+    }
+}
+
+fn lenient_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Null => Ok(None),
+        value => scalar_text(value)
+            .map(Some)
+            .ok_or_else(|| D::Error::custom("expected a string")),
+    }
+}
+
+/// A list of strings that also accepts null (no items) and a single scalar
+/// (one item).
+fn lenient_list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let items = match Value::deserialize(deserializer)? {
+        Value::Null => return Ok(Vec::new()),
+        Value::Sequence(items) => items,
+        value => vec![value],
+    };
+    items
+        .into_iter()
+        .filter(|item| !item.is_null())
+        .map(|item| scalar_text(item).ok_or_else(|| D::Error::custom("expected a list of strings")))
+        .collect()
+}
+
+/// Typed view of a note's YAML frontmatter. Keys Homebase does not know about
+/// are kept in `extras` and written back after the known ones, so editing a
+/// note from the backend never drops metadata added by the user or by plugins.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct NoteMeta {
+    #[serde(
+        default,
+        deserialize_with = "lenient_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub id: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub created: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub modified: Option<String>,
+    #[serde(default, deserialize_with = "lenient_list")]
+    pub projects: Vec<String>,
+    #[serde(default, deserialize_with = "lenient_list")]
+    pub topics: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_placed: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub daily_date: Option<String>,
+    /// Set while the note lives under `notes/archive/`; `archived_from` is the
+    /// path it had before, so unarchiving can put it back.
+    #[serde(
+        default,
+        deserialize_with = "lenient_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub archived_at: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "lenient_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub archived_from: Option<String>,
+    #[serde(flatten)]
+    pub extras: Mapping,
+}
+
+impl NoteMeta {
+    pub(crate) fn new(id: impl Into<String>, created: &str) -> Self {
+        NoteMeta {
+            id: Some(id.into()),
+            created: Some(created.to_string()),
+            modified: Some(created.to_string()),
+            ..NoteMeta::default()
+        }
+    }
+
+    /// The note id, treating a blank `id:` the same as a missing one.
+    pub(crate) fn id(&self) -> Option<&str> {
+        self.id
+            .as_deref()
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Note {
+    pub meta: NoteMeta,
+    pub body: String,
+}
+
+/// Splits a note into the raw YAML between the `---` fences and everything after
+/// the closing fence. Returns `None` when the file has no frontmatter block.
+pub(crate) fn split(contents: &str) -> Option<(&str, &str)> {
+    let raw = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+    let first_end = raw.find('\n')?;
+    if raw[..first_end].trim_end() != "---" {
+        return None;
+    }
+    let mut offset = first_end + 1;
+    for line in raw[offset..].split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return Some((&raw[first_end + 1..offset], &raw[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+impl Note {
+    pub(crate) fn new(meta: NoteMeta, body: impl Into<String>) -> Self {
+        Note {
+            meta,
+            body: body.into(),
+        }
+    }
+
+    /// Parses a note file. A file without frontmatter yields default metadata and
+    /// the whole file as body; malformed YAML is an error so callers never
+    /// overwrite metadata they could not read.
+    pub(crate) fn parse(contents: &str) -> Result<Self, serde_yaml::Error> {
+        let Some((yaml, rest)) = split(contents) else {
+            return Ok(Note::new(NoteMeta::default(), contents));
+        };
+        let meta = if yaml.trim().is_empty() {
+            NoteMeta::default()
+        } else {
+            serde_yaml::from_str(yaml)?
+        };
+        // The blank line after the closing fence is part of the layout, not the body.
+        let body = rest
+            .strip_prefix("\r\n")
+            .or_else(|| rest.strip_prefix('\n'))
+            .unwrap_or(rest);
+        Ok(Note::new(meta, body))
+    }
+
+    pub(crate) fn render(&self) -> Result<String, serde_yaml::Error> {
+        let yaml = serde_yaml::to_string(&self.meta)?;
+        Ok(format!("---\n{}---\n\n{}", yaml, self.body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_keeps_unknown_keys() {
+        let contents = "---\nid: abc\ncreated: 2024-05-01T10:00:00Z\nprojects:\n- p1\nrating: 5\nsource:\n  url: https://example.com\n---\n\n# Title\n\nBody\n";
+        let note = Note::parse(contents).unwrap();
+        assert_eq!(note.meta.id(), Some("abc"));
+        assert_eq!(note.meta.projects, ["p1"]);
+        assert_eq!(note.body, "# Title\n\nBody\n");
+        assert!(note.meta.extras.contains_key("rating"));
+
+        let rendered = note.render().unwrap();
+        assert_eq!(Note::parse(&rendered).unwrap(), note);
+        // Known keys first, unknown ones after, in their original order.
+        let id = rendered.find("id:").unwrap();
+        let rating = rendered.find("rating:").unwrap();
+        let source = rendered.find("source:").unwrap();
+        assert!(id < rating && rating < source);
+    }
+
+    #[test]
+    fn without_frontmatter_the_whole_file_is_body() {
+        let note = Note::parse("just text\n").unwrap();
+        assert_eq!(note.meta, NoteMeta::default());
+        assert_eq!(note.body, "just text\n");
+    }
+
+    #[test]
+    fn malformed_yaml_is_an_error() {
+        assert!(Note::parse("---\nid: [unclosed\n---\nbody").is_err());
+    }
+
+    #[test]
+    fn scalars_are_read_leniently() {
+        let note = Note::parse("---\nid: 123\nprojects:\ntopics: rust\n---\nbody").unwrap();
+        assert_eq!(note.meta.id(), Some("123"));
+        assert!(note.meta.projects.is_empty());
+        assert_eq!(note.meta.topics, ["rust"]);
+
+        assert!(Note::parse("---\nid:\n  nested: 1\n---\n").is_err());
+    }
+
+    #[test]
+    fn blank_id_counts_as_missing() {
+        let note = Note::parse("---\nid: '  '\n---\n").unwrap();
+        assert_eq!(note.meta.id(), None);
+    }
+}
@@ -0,0 +1,353 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use tauri::State;
+
+use crate::{
+    error::{VaultError, VaultResult},
+    frontmatter::{self, Note, NoteMeta},
+    periodic::{Period, PeriodKind},
+    state::VaultState,
+    vault::{
+        kind_from_relative_path, list_projects_internal, path_to_forward_slashes, write_atomic,
+    },
+};
+
+/// Temp files younger than this may belong to a write that is still in flight.
+const TEMP_FILE_GRACE: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckIssueKind {
+    /// The note could not be read at all (permissions, invalid UTF-8, ...).
+    /// Never repaired: fixing it without its contents would lose data.
+    Unreadable,
+    MissingFrontmatter,
+    InvalidFrontmatter,
+    MissingId,
+    DuplicateNoteId,
+    DuplicateProjectId,
+    /// A `.project.json` that cannot be read or parsed. The project is left
+    /// out of the sidebar until it is fixed by hand.
+    InvalidProject,
+    OrphanedTempFile,
+    MalformedDailyNote,
+    MalformedPeriodicNote,
+    UnknownNoteLocation,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckIssue {
+    pub kind: CheckIssueKind,
+    pub relative_path: String,
+    pub detail: String,
+    pub repaired: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckReport {
+    pub notes_scanned: usize,
+    pub issues: Vec<CheckIssue>,
+    pub repaired_count: usize,
+    pub unresolved_count: usize,
+}
+
+struct Checker<'a> {
+    vault_root: &'a Path,
+    repair: bool,
+    issues: Vec<CheckIssue>,
+}
+
+impl Checker<'_> {
+    fn report(
+        &mut self,
+        kind: CheckIssueKind,
+        relative_path: &str,
+        detail: String,
+        repaired: bool,
+    ) {
+        self.issues.push(CheckIssue {
+            kind,
+            relative_path: relative_path.to_string(),
+            detail,
+            repaired,
+        });
+    }
+
+    /// Runs `fix` only in repair mode and records whether it succeeded; a failed
+    /// repair is reported as unresolved with the failure appended to the detail.
+    fn report_with_fix(
+        &mut self,
+        kind: CheckIssueKind,
+        relative_path: &str,
+        detail: String,
+        fix: impl FnOnce() -> VaultResult<()>,
+    ) {
+        if !self.repair {
+            self.report(kind, relative_path, detail, false);
+            return;
+        }
+        match fix() {
+            Ok(()) => self.report(kind, relative_path, detail, true),
+            Err(e) => self.report(
+                kind,
+                relative_path,
+                format!("{} (repair failed: {})", detail, e),
+                false,
+            ),
+        }
+    }
+}
+
+/// Gives the note a fresh id, keeping the rest of its frontmatter intact.
+fn reassign_note_id(full: &Path, contents: &str) -> VaultResult<()> {
+    let mut note = Note::parse(contents).map_err(|e| VaultError::invalid_data(full, e))?;
+    note.meta.id = Some(Uuid::new_v4().to_string());
+    write_atomic(full, &note.render().map_err(VaultError::internal)?)
+}
+
+fn is_temp_file(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name.starts_with('.') && name.contains(".tmp-")
+}
+
+fn file_mtime(path: &Path) -> SystemTime {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+fn add_missing_frontmatter(full: &Path, rel: &str, contents: &str) -> VaultResult<()> {
+    let created: DateTime<Utc> = file_mtime(full).into();
+    let created = created.to_rfc3339();
+    let meta = match Period::from_relative_path(rel) {
+        Some(period) => period.meta(&created),
+        None => {
+            let mut meta = NoteMeta::new(Uuid::new_v4().to_string(), &created);
+            meta.user_placed = Some(!rel.starts_with("notes/inbox/"));
+            meta
+        }
+    };
+    let note = Note::new(meta, contents);
+    write_atomic(full, &note.render().map_err(VaultError::internal)?)
+}
+
+fn check_temp_files(checker: &mut Checker) {
+    let now = SystemTime::now();
+    let vault_root = checker.vault_root.to_path_buf();
+    for entry in WalkDir::new(&vault_root)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        if !entry.file_type().is_file() || !is_temp_file(entry.path()) {
+            continue;
+        }
+        let path = entry.path().to_path_buf();
+        let age = now.duration_since(file_mtime(&path)).unwrap_or_default();
+        if age < TEMP_FILE_GRACE {
+            continue;
+        }
+        let rel = path_to_forward_slashes(path.strip_prefix(&vault_root).unwrap_or(&path));
+        checker.report_with_fix(
+            CheckIssueKind::OrphanedTempFile,
+            &rel,
+            "Leftover temp file from an interrupted write".to_string(),
+            || fs::remove_file(&path).map_err(|e| VaultError::io(&path, e)),
+        );
+    }
+}
+
+fn check_notes(checker: &mut Checker) -> usize {
+    let notes_root = checker.vault_root.join("notes");
+    let mut scanned = 0;
+    // id -> notes carrying it, in walk order.
+    let mut ids: HashMap<String, Vec<(PathBuf, String)>> = HashMap::new();
+
+    for entry in WalkDir::new(&notes_root)
+        .follow_links(false)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        let full = entry.path().to_path_buf();
+        if !entry.file_type().is_file() || full.extension().and_then(|e| e.to_str()) != Some("md") {
+            continue;
+        }
+        scanned += 1;
+        let rel = path_to_forward_slashes(full.strip_prefix(checker.vault_root).unwrap_or(&full));
+
+        // A note in a periodic folder gets at most the malformed-name issue,
+        // even when it also sits somewhere no other kind would put it.
+        let periodic_kind = PeriodKind::ALL
+            .into_iter()
+            .find(|kind| rel.starts_with(&format!("{}/", kind.folder())));
+        match periodic_kind {
+            Some(kind) if Period::from_relative_path(&rel).is_none() => {
+                let issue_kind = match kind {
+                    PeriodKind::Day => CheckIssueKind::MalformedDailyNote,
+                    _ => CheckIssueKind::MalformedPeriodicNote,
+                };
+                checker.report(
+                    issue_kind,
+                    &rel,
+                    format!("File name must be a valid {} period", kind.key_format()),
+                    false,
+                );
+            }
+            Some(_) => {}
+            None if kind_from_relative_path(&rel) == "other" => checker.report(
+                CheckIssueKind::UnknownNoteLocation,
+                &rel,
+                "Note is outside notes/inbox, daily, folders, projects and archive".to_string(),
+                false,
+            ),
+            None => {}
+        }
+
+        let contents = match fs::read_to_string(&full) {
+            Ok(c) => c,
+            Err(e) => {
+                checker.report(
+                    CheckIssueKind::Unreadable,
+                    &rel,
+                    format!("Failed to read note: {}", e),
+                    false,
+                );
+                continue;
+            }
+        };
+
+        if frontmatter::split(&contents).is_none() {
+            checker.report_with_fix(
+                CheckIssueKind::MissingFrontmatter,
+                &rel,
+                "Note has no frontmatter block".to_string(),
+                || add_missing_frontmatter(&full, &rel, &contents),
+            );
+            continue;
+        }
+        match Note::parse(&contents) {
+            Err(e) => checker.report(
+                CheckIssueKind::InvalidFrontmatter,
+                &rel,
+                format!("Frontmatter is not valid YAML: {}", e),
+                false,
+            ),
+            Ok(note) => match note.meta.id() {
+                Some(id) => ids
+                    .entry(id.to_string())
+                    .or_default()
+                    .push((full.clone(), rel.clone())),
+                None => checker.report_with_fix(
+                    CheckIssueKind::MissingId,
+                    &rel,
+                    "Frontmatter has no id".to_string(),
+                    || reassign_note_id(&full, &contents),
+                ),
+            },
+        }
+    }
+
+    let mut duplicates: Vec<(String, Vec<(PathBuf, String)>)> = ids
+        .into_iter()
+        .filter(|(_, notes)| notes.len() > 1)
+        .collect();
+    duplicates.sort_by(|a, b| a.0.cmp(&b.0));
+    for (id, mut notes) in duplicates {
+        // The live copy keeps the id; archived copies and later files get new ones.
+        notes.sort_by_key(|(_, rel)| rel.starts_with("notes/archive/"));
+        let keeper = notes[0].1.clone();
+        for (full, rel) in notes.into_iter().skip(1) {
+            checker.report_with_fix(
+                CheckIssueKind::DuplicateNoteId,
+                &rel,
+                format!("Note id {} is also used by {}", id, keeper),
+                || {
+                    let contents =
+                        fs::read_to_string(&full).map_err(|e| VaultError::io(&full, e))?;
+                    reassign_note_id(&full, &contents)
+                },
+            );
+        }
+    }
+
+    scanned
+}
+
+fn check_projects(checker: &mut Checker) -> VaultResult<()> {
+    let mut seen: HashMap<String, String> = HashMap::new();
+    let mut projects = list_projects_internal(checker.vault_root)?;
+    projects.sort_by(|a, b| a.0.cmp(&b.0));
+    for (folder, meta) in projects {
+        let rel =
+            path_to_forward_slashes(folder.strip_prefix(checker.vault_root).unwrap_or(&folder));
+        let meta = match meta {
+            Ok(meta) => meta,
+            Err(e) => {
+                checker.report(
+                    CheckIssueKind::InvalidProject,
+                    &format!("{}/.project.json", rel),
+                    format!("Failed to read project: {}", e),
+                    false,
+                );
+                continue;
+            }
+        };
+        match seen.get(&meta.id) {
+            // Notes reference projects by id, so there is no safe way to pick
+            // which folder should keep it.
+            Some(first) => checker.report(
+                CheckIssueKind::DuplicateProjectId,
+                &format!("{}/.project.json", rel),
+                format!("Project id {} is also used by {}", meta.id, first),
+                false,
+            ),
+            None => {
+                seen.insert(meta.id, rel);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn vault_check(state: State<'_, VaultState>, repair: bool) -> VaultResult<CheckReport> {
+    state.with_vault(|vault| {
+        if repair {
+            vault.ensure_writable()?;
+        }
+
+        let mut checker = Checker {
+            vault_root: &vault.root,
+            repair,
+            issues: Vec::new(),
+        };
+        check_temp_files(&mut checker);
+        let notes_scanned = check_notes(&mut checker);
+        check_projects(&mut checker)?;
+        let issues = checker.issues;
+
+        let repaired_count = issues.iter().filter(|i| i.repaired).count();
+        if repaired_count > 0 {
+            vault.reindex()?;
+        }
+        let unresolved_count = issues.len() - repaired_count;
+        Ok(CheckReport {
+            notes_scanned,
+            issues,
+            repaired_count,
+            unresolved_count,
+        })
+    })
+}
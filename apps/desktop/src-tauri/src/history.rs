@@ -0,0 +1,243 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tauri::State;
+
+use crate::{
+    error::{VaultError, VaultResult},
+    frontmatter::Note,
+    state::VaultState,
+    vault::{content_hash, write_atomic, NoteById},
+};
+
+/// Saves closer together than this collapse into the snapshot taken at the
+/// start of the burst, so typing does not produce a revision per keystroke.
+const SNAPSHOT_INTERVAL_MS: i64 = 5 * 60 * 1000;
+/// Newest revisions kept per note.
+const MAX_REVISIONS: usize = 50;
+/// Revisions older than this are pruned even below `MAX_REVISIONS`.
+const MAX_REVISION_AGE_MS: i64 = 90 * 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Revision {
+    pub id: String,
+    pub created_at: String,
+    pub hash: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffLineKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    pub text: String,
+}
+
+struct StoredRevision {
+    path: PathBuf,
+    timestamp_ms: i64,
+    hash_prefix: String,
+}
+
+/// Ids come from frontmatter, so only accept ones that are safe as a single
+/// directory name.
+fn history_dir(vault_root: &Path, note_id: &str) -> Option<PathBuf> {
+    let safe = !note_id.is_empty()
+        && note_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    safe.then(|| vault_root.join(".homebase/history").join(note_id))
+}
+
+/// Revision files are named `<unix-ms>-<hash prefix>.md`.
+fn read_revisions(dir: &Path) -> Vec<StoredRevision> {
+    let mut revisions: Vec<StoredRevision> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let stem = path.file_name()?.to_str()?.strip_suffix(".md")?.to_string();
+            let (ts, hash_prefix) = stem.split_once('-')?;
+            Some(StoredRevision {
+                timestamp_ms: ts.parse().ok()?,
+                hash_prefix: hash_prefix.to_string(),
+                path,
+            })
+        })
+        .collect();
+    revisions.sort_by_key(|r| std::cmp::Reverse(r.timestamp_ms));
+    revisions
+}
+
+fn prune(revisions: Vec<StoredRevision>, now_ms: i64) -> VaultResult<()> {
+    for (i, revision) in revisions.into_iter().enumerate() {
+        if i >= MAX_REVISIONS || now_ms - revision.timestamp_ms > MAX_REVISION_AGE_MS {
+            fs::remove_file(&revision.path).map_err(|e| VaultError::io(&revision.path, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Snapshots the current contents of `note_path` before it is overwritten with
+/// `new_contents`. Throttled unless `force` is set; content already present in
+/// the history is never stored twice.
+pub(crate) fn record(
+    vault_root: &Path,
+    note_path: &Path,
+    new_contents: &str,
+    force: bool,
+) -> VaultResult<()> {
+    let previous = match fs::read_to_string(note_path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(VaultError::io(note_path, e)),
+    };
+    if previous == new_contents {
+        return Ok(());
+    }
+    let Some(dir) = Note::parse(&previous)
+        .ok()
+        .and_then(|note| note.meta.id().map(str::to_string))
+        .and_then(|id| history_dir(vault_root, &id))
+    else {
+        return Ok(());
+    };
+
+    let now_ms = Utc::now().timestamp_millis();
+    let hash = content_hash(previous.as_bytes());
+    let revisions = read_revisions(&dir);
+    let throttled = revisions
+        .first()
+        .is_some_and(|newest| now_ms - newest.timestamp_ms < SNAPSHOT_INTERVAL_MS);
+    let duplicate = revisions.iter().any(|r| hash.starts_with(&r.hash_prefix));
+    if duplicate || (throttled && !force) {
+        return Ok(());
+    }
+
+    let path = dir.join(format!("{}-{}.md", now_ms, &hash[..16]));
+    write_atomic(&path, &previous)?;
+    prune(read_revisions(&dir), now_ms)
+}
+
+fn find_revision(vault_root: &Path, note_id: &str, revision_id: &str) -> VaultResult<PathBuf> {
+    let dir = history_dir(vault_root, note_id)
+        .ok_or_else(|| VaultError::invalid_input("noteId", "is not a valid note id"))?;
+    read_revisions(&dir)
+        .into_iter()
+        .find(|r| r.path.file_stem().and_then(|s| s.to_str()) == Some(revision_id))
+        .map(|r| r.path)
+        .ok_or_else(|| VaultError::IdNotFound {
+            kind: "revision",
+            id: revision_id.to_string(),
+        })
+}
+
+fn read_file(path: &Path) -> VaultResult<String> {
+    fs::read_to_string(path).map_err(|e| VaultError::io(path, e))
+}
+
+#[tauri::command]
+pub fn vault_list_revisions(
+    state: State<'_, VaultState>,
+    note_id: String,
+) -> VaultResult<Vec<Revision>> {
+    state.with_vault(|vault| {
+        let Some(dir) = history_dir(&vault.root, &note_id) else {
+            return Ok(Vec::new());
+        };
+        read_revisions(&dir)
+            .into_iter()
+            .map(|r| {
+                let contents = fs::read(&r.path).map_err(|e| VaultError::io(&r.path, e))?;
+                let created_at = DateTime::<Utc>::from_timestamp_millis(r.timestamp_ms)
+                    .unwrap_or_default()
+                    .to_rfc3339();
+                Ok(Revision {
+                    id: r
+                        .path
+                        .file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string(),
+                    created_at,
+                    hash: content_hash(&contents),
+                    size: contents.len() as u64,
+                })
+            })
+            .collect()
+    })
+}
+
+/// Line diff from revision `from` to revision `to`, or to the note's current
+/// contents when `to` is omitted.
+#[tauri::command]
+pub fn vault_diff_revisions(
+    state: State<'_, VaultState>,
+    note_id: String,
+    from: String,
+    to: Option<String>,
+) -> VaultResult<Vec<DiffLine>> {
+    state.with_vault(|vault| {
+        let old = read_file(&find_revision(&vault.root, &note_id, &from)?)?;
+        let new = match to {
+            Some(to) => read_file(&find_revision(&vault.root, &note_id, &to)?)?,
+            None => {
+                let rel = vault.resolve_note_id(&note_id)?;
+                read_file(&vault.root.join(rel))?
+            }
+        };
+
+        let diff = TextDiff::from_lines(&old, &new);
+        Ok(diff
+            .iter_all_changes()
+            .map(|change| DiffLine {
+                kind: match change.tag() {
+                    ChangeTag::Equal => DiffLineKind::Equal,
+                    ChangeTag::Insert => DiffLineKind::Insert,
+                    ChangeTag::Delete => DiffLineKind::Delete,
+                },
+                old_line: change.old_index().map(|i| i + 1),
+                new_line: change.new_index().map(|i| i + 1),
+                text: change.value().trim_end_matches(['\r', '\n']).to_string(),
+            })
+            .collect())
+    })
+}
+
+/// Replaces the note with a stored revision. The contents being replaced are
+/// snapshotted first, so a restore can itself be undone.
+#[tauri::command]
+pub fn vault_restore_revision(
+    state: State<'_, VaultState>,
+    note_id: String,
+    revision_id: String,
+) -> VaultResult<NoteById> {
+    state.with_writable_vault(|vault| {
+        let contents = read_file(&find_revision(&vault.root, &note_id, &revision_id)?)?;
+        let relative_path = vault.resolve_note_id(&note_id)?;
+        let full = vault.root.join(&relative_path);
+        record(&vault.root, &full, &contents, true)?;
+        write_atomic(&full, &contents)?;
+        vault.index.refresh_note(&vault.root, &relative_path);
+        Ok(NoteById {
+            relative_path,
+            contents,
+        })
+    })
+}
@@ -0,0 +1,379 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use walkdir::WalkDir;
+
+use crate::{
+    error::VaultResult,
+    frontmatter::Note,
+    search_index::SearchIndex,
+    vault::{
+        kind_from_relative_path, list_projects_internal, path_to_forward_slashes, ProjectMeta,
+        VaultNoteEntry,
+    },
+};
+
+#[derive(Debug, Clone)]
+pub(crate) struct NoteRecord {
+    pub mtime_ms: i64,
+    pub size: u64,
+    pub id: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ProjectRecord {
+    pub folder_relative_path: String,
+    pub meta: ProjectMeta,
+}
+
+/// In-memory view of the vault, built when the vault is opened and kept
+/// current by the commands that mutate it. Changes made outside the app are
+/// picked up by `reconcile`.
+#[derive(Debug, Default)]
+pub(crate) struct VaultIndex {
+    notes: BTreeMap<String, NoteRecord>,
+    /// Note id -> relative path. When several files share an id, a live note
+    /// wins over an archived copy.
+    ids: HashMap<String, String>,
+    folders: BTreeSet<String>,
+    projects: BTreeMap<String, ProjectRecord>,
+    /// Kept in step with `notes`, best effort.
+    search: Option<SearchIndex>,
+}
+
+fn is_under(path: &str, dir: &str) -> bool {
+    path.strip_prefix(dir)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn is_archived(rel: &str) -> bool {
+    rel.starts_with("notes/archive/")
+}
+
+/// Reads the id from a note's frontmatter; unreadable notes simply have none.
+pub(crate) fn read_note_id(full: &Path) -> Option<String> {
+    let contents = fs::read_to_string(full).ok()?;
+    let note = Note::parse(&contents).ok()?;
+    note.meta.id().map(str::to_string)
+}
+
+fn mtime_ms(meta: &fs::Metadata) -> i64 {
+    meta.modified()
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+/// Every directory and markdown file below `notes/`, with its relative path.
+fn walk_notes(vault_root: &Path) -> impl Iterator<Item = (String, walkdir::DirEntry)> + '_ {
+    WalkDir::new(vault_root.join("notes"))
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(move |entry| {
+            let rel = path_to_forward_slashes(entry.path().strip_prefix(vault_root).ok()?);
+            let is_note = entry.file_type().is_file()
+                && entry.path().extension().and_then(|e| e.to_str()) == Some("md");
+            (is_note || entry.file_type().is_dir()).then_some((rel, entry))
+        })
+}
+
+fn load_projects(vault_root: &Path) -> VaultResult<BTreeMap<String, ProjectRecord>> {
+    let mut projects = BTreeMap::new();
+    for (folder, meta) in list_projects_internal(vault_root)? {
+        // A malformed `.project.json` hides that project until it is fixed;
+        // `vault_check` reports it.
+        let Ok(meta) = meta else {
+            continue;
+        };
+        let folder_relative_path = match folder.strip_prefix(vault_root) {
+            Ok(rel) => path_to_forward_slashes(rel),
+            Err(_) => continue,
+        };
+        projects.insert(
+            meta.id.clone(),
+            ProjectRecord {
+                folder_relative_path,
+                meta,
+            },
+        );
+    }
+    Ok(projects)
+}
+
+/// Stats and reads a note. Unreadable contents leave the note without an id
+/// or searchable text, but still listed.
+fn stat_note(full: &Path) -> Option<(NoteRecord, String)> {
+    let meta = fs::metadata(full).ok()?;
+    if !meta.is_file() {
+        return None;
+    }
+    let contents = fs::read_to_string(full).unwrap_or_default();
+    let id = Note::parse(&contents)
+        .ok()
+        .and_then(|note| note.meta.id().map(str::to_string));
+    Some((
+        NoteRecord {
+            mtime_ms: mtime_ms(&meta),
+            size: meta.len(),
+            id,
+        },
+        contents,
+    ))
+}
+
+impl VaultIndex {
+    /// Walks the notes and brings the search index in line with them: notes
+    /// whose mtime or size changed are re-indexed, vanished ones dropped.
+    pub(crate) fn build(vault_root: &Path, writable: bool) -> VaultResult<Self> {
+        let mut index = VaultIndex::default();
+        let search = SearchIndex::open(vault_root, writable);
+        // Indexed notes not (yet) found on disk; `None` when not reconciling.
+        let mut unseen = search
+            .as_ref()
+            .filter(|_| writable)
+            .and_then(|search| search.stored().ok());
+        let transaction = search
+            .as_ref()
+            .filter(|_| unseen.is_some())
+            .and_then(|search| search.begin().ok());
+
+        for (rel, entry) in walk_notes(vault_root) {
+            if entry.file_type().is_dir() {
+                if rel.starts_with("notes/folders/") {
+                    index.folders.insert(rel);
+                }
+                continue;
+            }
+            if let Some((record, contents)) = stat_note(entry.path()) {
+                if let (Some(search), Some(unseen)) = (&search, &mut unseen) {
+                    let stored = unseen.remove(&rel);
+                    if stored != Some((record.mtime_ms, record.size)) {
+                        let _ = search.upsert(&rel, &record, &contents);
+                    }
+                }
+                index.insert_note(rel, record);
+            }
+        }
+        if let (Some(search), Some(unseen)) = (&search, unseen) {
+            for rel in unseen.keys() {
+                let _ = search.remove(rel);
+            }
+        }
+        let _ = transaction.map(|transaction| transaction.commit());
+        index.search = search;
+        index.projects = load_projects(vault_root)?;
+        Ok(index)
+    }
+
+    /// Picks up notes, folders and projects changed outside the app (sync
+    /// clients, git, other editors) since the index was built. Notes are only
+    /// stat'ed; a note is re-read only when its mtime or size changed.
+    pub(crate) fn reconcile(&mut self, vault_root: &Path) -> VaultResult<()> {
+        let mut unseen: BTreeSet<String> = self.notes.keys().cloned().collect();
+        let mut changed = Vec::new();
+        let mut folders = BTreeSet::new();
+        for (rel, entry) in walk_notes(vault_root) {
+            if entry.file_type().is_dir() {
+                if rel.starts_with("notes/folders/") {
+                    folders.insert(rel);
+                }
+                continue;
+            }
+            unseen.remove(&rel);
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            let stored = self.notes.get(&rel).map(|r| (r.mtime_ms, r.size));
+            if stored != Some((mtime_ms(&meta), meta.len())) {
+                changed.push(rel);
+            }
+        }
+        self.folders = folders;
+        for rel in unseen.iter().chain(&changed) {
+            // Drops notes that are gone, re-reads the rest.
+            self.refresh_note(vault_root, rel);
+        }
+        self.projects = load_projects(vault_root)?;
+        Ok(())
+    }
+
+    pub(crate) fn list_notes(&self, include_archived: bool) -> Vec<VaultNoteEntry> {
+        let mut entries: Vec<VaultNoteEntry> = self
+            .notes
+            .iter()
+            .filter(|(rel, _)| include_archived || !is_archived(rel))
+            .map(|(rel, record)| VaultNoteEntry {
+                relative_path: rel.clone(),
+                kind: kind_from_relative_path(rel),
+                mtime_ms: record.mtime_ms,
+                size: record.size,
+            })
+            .collect();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.mtime_ms));
+        entries
+    }
+
+    /// Re-stats a single note after it was written or created; drops it from the
+    /// index if it no longer exists on disk.
+    pub(crate) fn refresh_note(&mut self, vault_root: &Path, rel: &str) {
+        match stat_note(&vault_root.join(rel)) {
+            Some((record, contents)) => {
+                if let Some(parent) = Path::new(rel).parent() {
+                    self.add_folder(&path_to_forward_slashes(parent));
+                }
+                if let Some(search) = &self.search {
+                    let _ = search.upsert(rel, &record, &contents);
+                }
+                self.remove_note(rel);
+                self.insert_note(rel.to_string(), record);
+            }
+            None => {
+                if let Some(search) = &self.search {
+                    let _ = search.remove(rel);
+                }
+                self.remove_note(rel);
+            }
+        }
+    }
+
+    pub(crate) fn move_note(&mut self, vault_root: &Path, from: &str, to: &str) {
+        if let Some(search) = &self.search {
+            let _ = search.remove(from);
+        }
+        self.remove_note(from);
+        self.refresh_note(vault_root, to);
+    }
+
+    pub(crate) fn search_index(&self) -> Option<&SearchIndex> {
+        self.search.as_ref()
+    }
+
+    pub(crate) fn note_path(&self, id: &str) -> Option<&str> {
+        self.ids.get(id).map(String::as_str)
+    }
+
+    fn insert_note(&mut self, rel: String, record: NoteRecord) {
+        if let Some(id) = &record.id {
+            let keep_existing = self
+                .ids
+                .get(id)
+                .is_some_and(|existing| !is_archived(existing) && is_archived(&rel));
+            if !keep_existing {
+                self.ids.insert(id.clone(), rel.clone());
+            }
+        }
+        self.notes.insert(rel, record);
+    }
+
+    fn remove_note(&mut self, rel: &str) {
+        let Some(id) = self.notes.remove(rel).and_then(|record| record.id) else {
+            return;
+        };
+        if self.ids.get(&id).map(String::as_str) != Some(rel) {
+            return;
+        }
+        self.ids.remove(&id);
+        // Hand the id to another copy, if any, preferring a live one.
+        let next = self
+            .notes
+            .iter()
+            .filter(|(_, record)| record.id.as_deref() == Some(id.as_str()))
+            .map(|(other, _)| other)
+            .min_by_key(|other| is_archived(other))
+            .cloned();
+        if let Some(next) = next {
+            self.ids.insert(id, next);
+        }
+    }
+
+    pub(crate) fn list_folders(&self) -> Vec<String> {
+        self.folders.iter().cloned().collect()
+    }
+
+    /// Records `rel` and all of its ancestors below `notes/folders/`.
+    pub(crate) fn add_folder(&mut self, rel: &str) {
+        let mut current = PathBuf::from(rel);
+        loop {
+            let current_str = path_to_forward_slashes(&current);
+            if !current_str.starts_with("notes/folders/") {
+                break;
+            }
+            self.folders.insert(current_str);
+            if !current.pop() {
+                break;
+            }
+        }
+    }
+
+    pub(crate) fn remove_folder(&mut self, rel: &str) {
+        self.folders.remove(rel);
+    }
+
+    /// Re-keys every note, folder and project below `from` after the directory
+    /// was renamed to `to`.
+    pub(crate) fn rename_dir(&mut self, from: &str, to: &str) {
+        let rekey = |path: &str| format!("{}{}", to, &path[from.len()..]);
+        if let Some(search) = &self.search {
+            let _ = search.rename_dir(from, to);
+        }
+
+        let moved_notes: Vec<String> = self
+            .notes
+            .keys()
+            .filter(|rel| is_under(rel, from))
+            .cloned()
+            .collect();
+        for rel in moved_notes {
+            if let Some(record) = self.notes.remove(&rel) {
+                let to_rel = rekey(&rel);
+                if let Some(id) = &record.id {
+                    if self.ids.get(id) == Some(&rel) {
+                        self.ids.insert(id.clone(), to_rel.clone());
+                    }
+                }
+                self.notes.insert(to_rel, record);
+            }
+        }
+
+        let moved_folders: Vec<String> = self
+            .folders
+            .iter()
+            .filter(|rel| rel.as_str() == from || is_under(rel, from))
+            .cloned()
+            .collect();
+        for rel in moved_folders {
+            self.folders.remove(&rel);
+            self.folders.insert(rekey(&rel));
+        }
+
+        for project in self.projects.values_mut() {
+            let folder = &project.folder_relative_path;
+            if folder == from || is_under(folder, from) {
+                project.folder_relative_path = rekey(folder);
+            }
+        }
+    }
+
+    pub(crate) fn projects(&self) -> impl Iterator<Item = &ProjectRecord> {
+        self.projects.values()
+    }
+
+    pub(crate) fn project(&self, id: &str) -> Option<&ProjectRecord> {
+        self.projects.get(id)
+    }
+
+    pub(crate) fn upsert_project(&mut self, folder_relative_path: String, meta: ProjectMeta) {
+        self.projects.insert(
+            meta.id.clone(),
+            ProjectRecord {
+                folder_relative_path,
+                meta,
+            },
+        );
+    }
+}
@@ -0,0 +1,472 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fs, io, path::Path};
+use tauri::State;
+
+use crate::{
+    error::{VaultError, VaultResult},
+    moves::move_file,
+    state::{OpenVault, VaultState},
+    trash::{self, TrashKind},
+    vault::{validate_relative_path, write_atomic},
+};
+
+const JOURNAL_FILE: &str = ".homebase/journal.json";
+/// Older entries are dropped once the undo stack grows past this.
+const MAX_ENTRIES: usize = 100;
+
+/// One reversible change to the vault's structure. Paths are vault-relative.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Step {
+    /// A note or directory was renamed or moved.
+    Move {
+        from: String,
+        to: String,
+    },
+    /// A file was rewritten in place, e.g. frontmatter stamped on archive.
+    Write {
+        path: String,
+        before: String,
+        after: String,
+    },
+    CreateDir {
+        path: String,
+    },
+    RemoveDir {
+        path: String,
+    },
+    /// A new file was written. Its contents are kept so that removing it on
+    /// undo loses nothing a redo cannot bring back.
+    CreateFile {
+        path: String,
+        contents: String,
+    },
+    RemoveFile {
+        path: String,
+        contents: String,
+    },
+    /// `path` went into trash entry `entry_id`.
+    Trash {
+        path: String,
+        entry_id: String,
+    },
+    /// Trash entry `entry_id` came back out at `path`.
+    Untrash {
+        entry_id: String,
+        path: String,
+    },
+}
+
+impl Step {
+    fn inverse(self) -> Step {
+        match self {
+            Step::Move { from, to } => Step::Move { from: to, to: from },
+            Step::Write {
+                path,
+                before,
+                after,
+            } => Step::Write {
+                path,
+                before: after,
+                after: before,
+            },
+            Step::CreateDir { path } => Step::RemoveDir { path },
+            Step::RemoveDir { path } => Step::CreateDir { path },
+            Step::CreateFile { path, contents } => Step::RemoveFile { path, contents },
+            Step::RemoveFile { path, contents } => Step::CreateFile { path, contents },
+            Step::Trash { path, entry_id } => Step::Untrash { entry_id, path },
+            Step::Untrash { entry_id, path } => Step::Trash { path, entry_id },
+        }
+    }
+}
+
+/// Undoing a list of steps means applying their inverses newest first.
+fn invert(steps: Vec<Step>) -> Vec<Step> {
+    steps.into_iter().rev().map(Step::inverse).collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub label: String,
+    pub at: String,
+    /// In the order they were originally applied.
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Journal {
+    #[serde(default)]
+    undo: Vec<JournalEntry>,
+    #[serde(default)]
+    redo: Vec<JournalEntry>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalStatus {
+    pub undo_label: Option<String>,
+    pub redo_label: Option<String>,
+}
+
+fn load(vault_root: &Path) -> VaultResult<Journal> {
+    let path = vault_root.join(JOURNAL_FILE);
+    match fs::read_to_string(&path) {
+        Ok(raw) => serde_json::from_str(&raw).map_err(|e| VaultError::invalid_data(&path, e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Journal::default()),
+        Err(e) => Err(VaultError::io(&path, e)),
+    }
+}
+
+fn save(vault_root: &Path, journal: &Journal) -> VaultResult<()> {
+    let raw = serde_json::to_string_pretty(journal).map_err(VaultError::internal)?;
+    write_atomic(&vault_root.join(JOURNAL_FILE), &raw)
+}
+
+/// Journals the steps the current command recorded as one undoable entry.
+/// Best effort: a journal that cannot be written must not fail the change it
+/// describes, which has already happened.
+pub(crate) fn commit(vault: &mut OpenVault, label: &str) {
+    let steps = vault.take_steps();
+    if steps.is_empty() {
+        return;
+    }
+    let mut journal = load(&vault.root).unwrap_or_default();
+    journal.undo.push(JournalEntry {
+        label: label.to_string(),
+        at: Utc::now().to_rfc3339(),
+        steps,
+    });
+    let excess = journal.undo.len().saturating_sub(MAX_ENTRIES);
+    journal.undo.drain(..excess);
+    journal.redo.clear();
+    let _ = save(&vault.root, &journal);
+}
+
+/// Drops the entries that restore from or re-trash into a purged trash entry,
+/// since they can no longer be replayed, together with everything below them
+/// on the same stack, which could only be reached by replaying them first.
+pub(crate) fn forget_trash_entries(vault_root: &Path, purged: &HashSet<String>) -> VaultResult<()> {
+    if purged.is_empty() {
+        return Ok(());
+    }
+    let mut journal = load(vault_root)?;
+    let uses_purged = |entry: &JournalEntry| {
+        entry.steps.iter().any(|step| match step {
+            Step::Trash { entry_id, .. } | Step::Untrash { entry_id, .. } => {
+                purged.contains(entry_id)
+            }
+            _ => false,
+        })
+    };
+    let mut changed = false;
+    for stack in [&mut journal.undo, &mut journal.redo] {
+        if let Some(last) = stack.iter().rposition(uses_purged) {
+            stack.drain(..=last);
+            changed = true;
+        }
+    }
+    if changed {
+        save(vault_root, &journal)?;
+    }
+    Ok(())
+}
+
+fn move_path(vault: &OpenVault, from: &str, to: &str) -> VaultResult<()> {
+    let from_full = vault.root.join(validate_relative_path(from)?);
+    let to_full = vault.root.join(validate_relative_path(to)?);
+    if !from_full.is_dir() {
+        return move_file(&from_full, &to_full);
+    }
+    if to_full.exists() {
+        return Err(VaultError::already_exists(to));
+    }
+    fs::rename(&from_full, &to_full).map_err(|e| VaultError::io(from, e))
+}
+
+/// Applies one step, refusing when the vault no longer looks the way the step
+/// expects. Trash steps are updated with the entry id or path they produced.
+fn apply(vault: &mut OpenVault, step: &mut Step) -> VaultResult<()> {
+    match step {
+        Step::Move { from, to } => move_path(vault, from, to),
+        Step::Write {
+            path,
+            before,
+            after,
+        } => {
+            let full = vault.root.join(validate_relative_path(path)?);
+            let current = match fs::read_to_string(&full) {
+                Ok(current) => Some(current),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                Err(e) => return Err(VaultError::io(&*path, e)),
+            };
+            if current.as_deref() != Some(before.as_str()) {
+                return Err(VaultError::Conflict {
+                    path: path.clone(),
+                    current_contents: current,
+                });
+            }
+            write_atomic(&full, after)
+        }
+        Step::CreateDir { path } => {
+            let full = vault.root.join(validate_relative_path(path)?);
+            fs::create_dir(&full).map_err(|e| match e.kind() {
+                io::ErrorKind::AlreadyExists => VaultError::already_exists(&*path),
+                _ => VaultError::io(&*path, e),
+            })
+        }
+        Step::RemoveDir { path } => {
+            let full = vault.root.join(validate_relative_path(path)?);
+            let mut children = fs::read_dir(&full).map_err(|e| VaultError::io(&*path, e))?;
+            if children.next().is_some() {
+                return Err(VaultError::NotEmpty { path: path.clone() });
+            }
+            fs::remove_dir(&full).map_err(|e| VaultError::io(&*path, e))
+        }
+        Step::CreateFile { path, contents } => {
+            let full = vault.root.join(validate_relative_path(path)?);
+            if full.exists() {
+                return Err(VaultError::already_exists(&*path));
+            }
+            write_atomic(&full, contents)
+        }
+        Step::RemoveFile { path, contents } => {
+            let full = vault.root.join(validate_relative_path(path)?);
+            let current = fs::read_to_string(&full).map_err(|e| VaultError::io(&*path, e))?;
+            if current != *contents {
+                return Err(VaultError::Conflict {
+                    path: path.clone(),
+                    current_contents: Some(current),
+                });
+            }
+            fs::remove_file(&full).map_err(|e| VaultError::io(&*path, e))
+        }
+        Step::Trash { path, entry_id } => {
+            let full = vault.root.join(validate_relative_path(path)?);
+            if !full.exists() {
+                return Err(VaultError::not_found(&*path));
+            }
+            let kind = if full.is_dir() {
+                TrashKind::Folder
+            } else if path.starts_with("assets/") {
+                TrashKind::Asset
+            } else {
+                TrashKind::Note
+            };
+            *entry_id = trash::move_to_trash(vault, path, kind)?.id;
+            Ok(())
+        }
+        Step::Untrash { entry_id, path } => {
+            *path = trash::restore_entry(vault, entry_id)?;
+            Ok(())
+        }
+    }
+}
+
+/// Undoes what the current command has done so far, newest step first, when
+/// it fails part-way. Best effort: a step that cannot be undone is skipped.
+pub(crate) fn roll_back(vault: &mut OpenVault) {
+    for mut step in invert(vault.take_steps()) {
+        let _ = apply(vault, &mut step);
+    }
+    let _ = vault.reindex();
+}
+
+/// Applies `steps` in order. If one fails, the ones already applied are put
+/// back. Either way the steps come back updated, so the entry can be kept.
+fn run(vault: &mut OpenVault, steps: Vec<Step>) -> (Vec<Step>, VaultResult<()>) {
+    let mut done: Vec<Step> = Vec::new();
+    let mut rest = steps.into_iter();
+    while let Some(mut step) = rest.next() {
+        if let Err(e) = apply(vault, &mut step) {
+            let mut reverted: Vec<Step> = Vec::new();
+            for applied in done.into_iter().rev() {
+                let mut back = applied.inverse();
+                let _ = apply(vault, &mut back);
+                reverted.push(back.inverse());
+            }
+            reverted.reverse();
+            reverted.push(step);
+            reverted.extend(rest);
+            return (reverted, Err(e));
+        }
+        done.push(step);
+    }
+    (done, Ok(()))
+}
+
+/// Pops the newest entry off the undo (or redo) stack, replays it and moves
+/// it to the other stack. A failed replay leaves the entry where it was.
+fn replay(vault: &mut OpenVault, undo: bool) -> VaultResult<Option<JournalEntry>> {
+    let mut journal = load(&vault.root)?;
+    let Journal {
+        undo: undo_stack,
+        redo: redo_stack,
+    } = &mut journal;
+    let (from, to) = if undo {
+        (undo_stack, redo_stack)
+    } else {
+        (redo_stack, undo_stack)
+    };
+    let Some(entry) = from.pop() else {
+        return Ok(None);
+    };
+
+    let steps = if undo {
+        invert(entry.steps)
+    } else {
+        entry.steps
+    };
+    let (steps, result) = run(vault, steps);
+    let entry = JournalEntry {
+        steps: if undo { invert(steps) } else { steps },
+        ..entry
+    };
+    if result.is_ok() {
+        to.push(entry.clone());
+    } else {
+        from.push(entry.clone());
+    }
+
+    // Undo touches arbitrary paths, so rebuild the index wholesale.
+    let reindexed = vault.reindex();
+    save(&vault.root, &journal)?;
+    result?;
+    reindexed?;
+    Ok(Some(entry))
+}
+
+/// Reverts the most recent structural change and returns it, or `None` when
+/// there is nothing to undo.
+#[tauri::command]
+pub fn vault_undo(state: State<'_, VaultState>) -> VaultResult<Option<JournalEntry>> {
+    state.with_writable_vault(|vault| replay(vault, true))
+}
+
+#[tauri::command]
+pub fn vault_redo(state: State<'_, VaultState>) -> VaultResult<Option<JournalEntry>> {
+    state.with_writable_vault(|vault| replay(vault, false))
+}
+
+#[tauri::command]
+pub fn vault_journal_status(state: State<'_, VaultState>) -> VaultResult<JournalStatus> {
+    state.with_vault(|vault| {
+        let journal = load(&vault.root)?;
+        Ok(JournalStatus {
+            undo_label: journal.undo.last().map(|e| e.label.clone()),
+            redo_label: journal.redo.last().map(|e| e.label.clone()),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples() -> Vec<Step> {
+        vec![
+            Step::Move {
+                from: "notes/inbox/a.md".into(),
+                to: "notes/archive/a.md".into(),
+            },
+            Step::Write {
+                path: "notes/inbox/b.md".into(),
+                before: "old".into(),
+                after: "new".into(),
+            },
+            Step::CreateDir {
+                path: "notes/folders/x".into(),
+            },
+            Step::CreateFile {
+                path: "notes/folders/x/c.md".into(),
+                contents: "c".into(),
+            },
+            Step::Trash {
+                path: "notes/inbox/d.md".into(),
+                entry_id: "e1".into(),
+            },
+        ]
+    }
+
+    #[test]
+    fn inverse_swaps_each_step() {
+        assert_eq!(
+            samples()[1].clone().inverse(),
+            Step::Write {
+                path: "notes/inbox/b.md".into(),
+                before: "new".into(),
+                after: "old".into(),
+            }
+        );
+        assert_eq!(
+            samples()[4].clone().inverse(),
+            Step::Untrash {
+                entry_id: "e1".into(),
+                path: "notes/inbox/d.md".into(),
+            }
+        );
+        for step in samples() {
+            assert_eq!(step.clone().inverse().inverse(), step);
+        }
+    }
+
+    #[test]
+    fn invert_reverses_the_order() {
+        let inverted = invert(samples());
+        assert_eq!(
+            inverted[0],
+            Step::Untrash {
+                entry_id: "e1".into(),
+                path: "notes/inbox/d.md".into(),
+            }
+        );
+        assert_eq!(
+            inverted[1],
+            Step::RemoveFile {
+                path: "notes/folders/x/c.md".into(),
+                contents: "c".into(),
+            }
+        );
+        assert_eq!(invert(inverted), samples());
+    }
+
+    #[test]
+    fn forgetting_a_trash_entry_drops_it_and_everything_older() {
+        let root = std::env::temp_dir().join(format!("homebase-journal-{}", std::process::id()));
+        let entry = |label: &str, steps: Vec<Step>| JournalEntry {
+            label: label.into(),
+            at: String::new(),
+            steps,
+        };
+        let journal = Journal {
+            undo: vec![
+                entry("oldest", vec![samples()[0].clone()]),
+                entry("trash", vec![samples()[4].clone()]),
+                entry("newest", vec![samples()[2].clone()]),
+            ],
+            redo: vec![entry("redo", vec![samples()[1].clone()])],
+        };
+        save(&root, &journal).unwrap();
+
+        forget_trash_entries(&root, &HashSet::from(["e1".to_string()])).unwrap();
+        let journal = load(&root).unwrap();
+        let labels: Vec<&str> = journal.undo.iter().map(|e| e.label.as_str()).collect();
+        assert_eq!(labels, ["newest"]);
+        assert_eq!(journal.redo.len(), 1);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn steps_serialize_tagged_in_camel_case() {
+        let json = serde_json::to_value(&samples()[4]).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "kind": "trash", "path": "notes/inbox/d.md", "entryId": "e1" })
+        );
+    }
+}
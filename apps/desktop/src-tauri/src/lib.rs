@@ -1,28 +1,109 @@
+mod assets;
+mod batch;
+mod error;
+mod frontmatter;
+mod fsck;
+mod history;
+mod index;
+mod journal;
+mod lock;
+mod merge;
+mod migrations;
+mod moves;
+mod naming;
+mod periodic;
+mod registry;
+mod search;
+mod search_index;
+mod settings;
+mod split;
+mod state;
+mod templates;
+mod thumbs;
+mod trash;
 mod vault;
 
+use tauri::Manager;
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .manage(state::VaultState::default())
+        .register_asynchronous_uri_scheme_protocol(
+            thumbs::THUMB_SCHEME,
+            |ctx, request, responder| {
+                let app = ctx.app_handle().clone();
+                std::thread::spawn(move || {
+                    let state = app.state::<state::VaultState>();
+                    responder.respond(thumbs::respond(&state, request.uri().path()));
+                });
+            },
+        )
         .invoke_handler(tauri::generate_handler![
+            registry::vault_list_vaults,
+            registry::vault_add_vault,
+            registry::vault_remove_vault,
+            registry::vault_open_vault,
+            registry::vault_switch_vault,
             vault::vault_init,
             vault::vault_list_notes,
+            vault::vault_refresh_index,
             vault::vault_read_note,
+            vault::vault_resolve_note_id,
+            vault::vault_read_note_by_id,
             vault::vault_create_note,
             vault::vault_create_note_from_markdown,
             vault::vault_create_note_in_inbox,
-            vault::vault_create_daily_note,
+            periodic::vault_create_daily_note,
+            periodic::vault_create_periodic_note,
+            periodic::vault_get_period,
+            periodic::vault_previous_period,
+            periodic::vault_next_period,
+            periodic::vault_list_daily_notes_in_period,
+            templates::vault_list_templates,
+            templates::vault_create_note_from_template,
             vault::vault_write_note,
+            vault::vault_force_write_note,
             vault::vault_archive_note,
+            vault::vault_unarchive_note,
             vault::vault_move_note,
+            naming::vault_rename_note,
+            merge::vault_duplicate_note,
+            merge::vault_merge_notes,
+            split::vault_split_note,
             vault::vault_list_folders,
             vault::vault_create_folder,
             vault::vault_rename_folder,
             vault::vault_delete_folder,
             vault::vault_list_projects,
             vault::vault_create_project,
-            vault::vault_update_project
+            vault::vault_update_project,
+            fsck::vault_check,
+            history::vault_list_revisions,
+            history::vault_diff_revisions,
+            history::vault_restore_revision,
+            settings::vault_get_settings,
+            settings::vault_update_settings,
+            trash::vault_trash_note,
+            trash::vault_trash_folder,
+            trash::vault_list_trash,
+            trash::vault_restore_from_trash,
+            trash::vault_empty_trash,
+            batch::vault_batch,
+            journal::vault_undo,
+            journal::vault_redo,
+            journal::vault_journal_status,
+            assets::vault_import_attachment,
+            assets::vault_scan_assets,
+            assets::vault_gc_assets,
+            search::vault_search
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                app.state::<state::VaultState>().close();
+            }
+        });
 }
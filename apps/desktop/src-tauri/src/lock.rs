@@ -0,0 +1,293 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    thread,
+    time::Duration,
+};
+use uuid::Uuid;
+
+use crate::error::{VaultError, VaultResult};
+
+const LOCK_FILE: &str = ".homebase/vault.lock";
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+/// A lock whose heartbeat is older than this is considered abandoned even if
+/// its PID cannot be checked (e.g. it was taken on another machine).
+const STALE_AFTER: Duration = Duration::from_secs(60);
+/// How long a stale-lock takeover waits before checking it still owns the
+/// lock, so a competing takeover's rename has landed by then.
+const TAKEOVER_SETTLE: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockInfo {
+    pub instance_id: String,
+    pub pid: u32,
+    pub hostname: Option<String>,
+    pub acquired_at: String,
+    pub heartbeat_at: String,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum LockStatus {
+    Held,
+    ReadOnly { holder: LockInfo },
+}
+
+/// Set by the heartbeat when another instance turns out to own the lock.
+type TakenOver = Arc<Mutex<Option<LockInfo>>>;
+
+/// The advisory lock on an open vault. Dropping it stops the heartbeat and
+/// removes the lock file if this process still owns it.
+pub(crate) struct VaultLock {
+    path: PathBuf,
+    instance_id: String,
+    status: LockStatus,
+    taken_over: TakenOver,
+    stop_heartbeat: Arc<AtomicBool>,
+}
+
+impl VaultLock {
+    /// `ReadOnly` from the start, or once the heartbeat has found another
+    /// instance holding the lock (e.g. after we were suspended past
+    /// `STALE_AFTER`).
+    pub(crate) fn status(&self) -> LockStatus {
+        let taken_over = self
+            .taken_over
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        match &*taken_over {
+            Some(holder) => LockStatus::ReadOnly {
+                holder: holder.clone(),
+            },
+            None => self.status.clone(),
+        }
+    }
+
+    /// Guards every mutating command: fails while the vault is opened read-only
+    /// because another Homebase process holds its lock.
+    pub(crate) fn ensure_writable(&self) -> VaultResult<()> {
+        match self.status() {
+            LockStatus::Held => Ok(()),
+            LockStatus::ReadOnly { holder } => Err(VaultError::ReadOnly { holder }),
+        }
+    }
+}
+
+impl Drop for VaultLock {
+    fn drop(&mut self) {
+        self.stop_heartbeat.store(true, Ordering::SeqCst);
+        if matches!(self.status(), LockStatus::Held)
+            && read_lock(&self.path).is_some_and(|info| info.instance_id == self.instance_id)
+        {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn lock_path(vault_root: &Path) -> PathBuf {
+    vault_root.join(LOCK_FILE)
+}
+
+#[cfg(unix)]
+fn current_hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    // SAFETY: the buffer is valid for `buf.len()` bytes and gethostname writes at
+    // most that many.
+    let rc = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    if rc != 0 {
+        return None;
+    }
+    let end = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    Some(String::from_utf8_lossy(&buf[..end]).to_string())
+}
+
+#[cfg(not(unix))]
+fn current_hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
+
+#[cfg(unix)]
+fn pid_is_alive(pid: u32) -> Option<bool> {
+    let pid = libc::pid_t::try_from(pid).ok()?;
+    // SAFETY: signal 0 performs the permission and existence checks only.
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return Some(true);
+    }
+    let errno = std::io::Error::last_os_error().raw_os_error();
+    Some(errno == Some(libc::EPERM))
+}
+
+#[cfg(not(unix))]
+fn pid_is_alive(_pid: u32) -> Option<bool> {
+    None
+}
+
+fn is_stale(info: &LockInfo) -> bool {
+    let same_host = info.hostname.is_some() && info.hostname == current_hostname();
+    if same_host && pid_is_alive(info.pid) == Some(false) {
+        return true;
+    }
+    match DateTime::parse_from_rfc3339(&info.heartbeat_at) {
+        Ok(heartbeat) => {
+            let age = Utc::now().signed_duration_since(heartbeat.with_timezone(&Utc));
+            age.to_std().map(|age| age > STALE_AFTER).unwrap_or(false)
+        }
+        Err(_) => true,
+    }
+}
+
+fn read_lock(path: &Path) -> Option<LockInfo> {
+    let raw = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+/// Creates the lock file only if it does not exist yet, so two processes racing
+/// for the same vault cannot both win.
+fn try_create_lock(path: &Path, info: &LockInfo) -> VaultResult<bool> {
+    let raw = serde_json::to_string_pretty(info).map_err(VaultError::internal)?;
+    match fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+    {
+        Ok(mut file) => {
+            file.write_all(raw.as_bytes())
+                .and_then(|_| file.sync_all())
+                .map_err(|e| VaultError::io(path, e))?;
+            Ok(true)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+        Err(e) => Err(VaultError::io(path, e)),
+    }
+}
+
+/// Replaces the lock file in one `rename`, so there is never a moment without
+/// one for another process's `create_new` to slip into.
+fn replace_lock(path: &Path, info: &LockInfo) -> VaultResult<()> {
+    let raw = serde_json::to_string_pretty(info).map_err(VaultError::internal)?;
+    let tmp = path.with_extension(format!("tmp-{}", info.instance_id));
+    fs::write(&tmp, raw)
+        .and_then(|_| fs::rename(&tmp, path))
+        .map_err(|e| {
+            let _ = fs::remove_file(&tmp);
+            VaultError::io(path, e)
+        })
+}
+
+fn spawn_heartbeat(
+    path: PathBuf,
+    instance_id: String,
+    stop: Arc<AtomicBool>,
+    taken_over: TakenOver,
+) {
+    let give_up = move |holder: LockInfo| {
+        *taken_over.lock().unwrap_or_else(PoisonError::into_inner) = Some(holder);
+    };
+    thread::spawn(move || loop {
+        thread::sleep(HEARTBEAT_INTERVAL);
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+        let Some(mut info) = read_lock(&path) else {
+            break;
+        };
+        // Someone else took over (e.g. we were suspended past STALE_AFTER).
+        if info.instance_id != instance_id {
+            give_up(info);
+            break;
+        }
+        info.heartbeat_at = Utc::now().to_rfc3339();
+        let _ = replace_lock(&path, &info);
+        // A takeover landing between the read and our rename was just
+        // overwritten, and its owner will see our id and give up. One landing
+        // after it wins, as in `acquire`: the last rename owns the lock.
+        thread::sleep(TAKEOVER_SETTLE);
+        match read_lock(&path) {
+            Some(holder) if holder.instance_id != instance_id => {
+                give_up(holder);
+                break;
+            }
+            _ => {}
+        }
+    });
+}
+
+/// Takes the advisory lock for `vault_root`, falling back to read-only when a
+/// live process already owns it.
+pub(crate) fn acquire(vault_root: &Path) -> VaultResult<VaultLock> {
+    let path = lock_path(vault_root);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| VaultError::io(parent, e))?;
+    }
+
+    let now = Utc::now().to_rfc3339();
+    let info = LockInfo {
+        instance_id: Uuid::new_v4().to_string(),
+        pid: std::process::id(),
+        hostname: current_hostname(),
+        acquired_at: now.clone(),
+        heartbeat_at: now,
+    };
+
+    let mut created = try_create_lock(&path, &info)?;
+    if !created {
+        match read_lock(&path) {
+            Some(holder) if !is_stale(&holder) => {
+                return Ok(VaultLock {
+                    path,
+                    instance_id: info.instance_id,
+                    status: LockStatus::ReadOnly { holder },
+                    taken_over: TakenOver::default(),
+                    stop_heartbeat: Arc::new(AtomicBool::new(true)),
+                });
+            }
+            _ => {
+                // Overwrite rather than remove-and-create: another process that
+                // also judged the lock stale could otherwise remove the lock we
+                // just created. Whichever rename lands last wins; give the other
+                // a moment to land, then check that it was ours.
+                replace_lock(&path, &info)?;
+                thread::sleep(TAKEOVER_SETTLE);
+                match read_lock(&path) {
+                    Some(now) if now.instance_id == info.instance_id => created = true,
+                    Some(holder) => {
+                        return Ok(VaultLock {
+                            path,
+                            instance_id: info.instance_id,
+                            status: LockStatus::ReadOnly { holder },
+                            taken_over: TakenOver::default(),
+                            stop_heartbeat: Arc::new(AtomicBool::new(true)),
+                        });
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+    if !created {
+        return Err(VaultError::LockContended);
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let taken_over = TakenOver::default();
+    spawn_heartbeat(
+        path.clone(),
+        info.instance_id.clone(),
+        stop.clone(),
+        taken_over.clone(),
+    );
+    Ok(VaultLock {
+        path,
+        instance_id: info.instance_id,
+        status: LockStatus::Held,
+        taken_over,
+        stop_heartbeat: stop,
+    })
+}
@@ -0,0 +1,356 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    error::{VaultError, VaultResult},
+    frontmatter::Note,
+    history,
+    journal::{self, Step},
+    moves::CollisionPolicy,
+    naming,
+    state::{OpenVault, VaultState},
+    vault::{
+        archive_note, archive_target, collision_policy, read_parsed_note, write_atomic,
+        CreateNoteResult,
+    },
+};
+
+/// Appends " (copy)" to the note's first heading, or gives it one.
+fn copy_title(body: &str) -> (String, String) {
+    match naming::first_heading_range(body) {
+        Some(range) => {
+            let title = format!("{} (copy)", &body[range.clone()]);
+            let body = format!("{}{}{}", &body[..range.start], title, &body[range.end..]);
+            (title, body)
+        }
+        None => {
+            let title = "Untitled (copy)".to_string();
+            let body = format!("# {}\n\n{}", title, body);
+            (title, body)
+        }
+    }
+}
+
+/// Copies a note next to the original under a fresh id. Copies of daily and
+/// archived notes go to the inbox, since those folders are managed by date
+/// and by archiving.
+#[tauri::command]
+pub fn vault_duplicate_note(
+    state: State<'_, VaultState>,
+    relative_path: String,
+) -> VaultResult<CreateNoteResult> {
+    state.with_writable_vault(|vault| {
+        let (rel, mut note) = read_parsed_note(vault, &relative_path)?;
+        let id = Uuid::new_v4().to_string();
+        let now_iso = Utc::now().to_rfc3339();
+
+        let target_dir = naming::derived_note_dir(&rel);
+
+        let (title, body) = copy_title(&note.body);
+        note.meta.id = Some(id.clone());
+        note.meta.created = Some(now_iso.clone());
+        note.meta.modified = Some(now_iso);
+        note.meta.daily_date = None;
+        note.meta.archived_at = None;
+        note.meta.archived_from = None;
+        if target_dir == "notes/inbox" {
+            note.meta.user_placed = Some(false);
+        }
+        let contents = Note::new(note.meta, body)
+            .render()
+            .map_err(VaultError::internal)?;
+
+        let target_rel = naming::new_note_path(&vault.root, &target_dir, &title, &id);
+        write_atomic(&vault.root.join(&target_rel), &contents)?;
+        vault.record_step(Step::CreateFile {
+            path: target_rel.clone(),
+            contents: contents.clone(),
+        });
+        vault.index.refresh_note(&vault.root, &target_rel);
+        journal::commit(vault, "Duplicate note");
+        Ok(CreateNoteResult {
+            id,
+            relative_path: target_rel,
+            contents,
+        })
+    })
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeNotesArgs {
+    pub target: String,
+    pub sources: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeNotesResult {
+    pub relative_path: String,
+    pub contents: String,
+    /// Where each source ended up in the archive, in the order given.
+    pub archived: Vec<String>,
+    /// Notes whose links were rewritten to point at the target.
+    pub relinked: Vec<String>,
+}
+
+fn push_unique(into: &mut Vec<String>, values: &[String]) {
+    for value in values {
+        if !into.contains(value) {
+            into.push(value.clone());
+        }
+    }
+}
+
+/// The earlier of two `created` stamps; ones that do not parse never win.
+fn earliest(a: Option<String>, b: Option<&String>) -> Option<String> {
+    let parse = |s: &str| DateTime::parse_from_rfc3339(s).ok();
+    match (a, b) {
+        (Some(a), Some(b)) => match (parse(&a), parse(b)) {
+            (Some(pa), Some(pb)) if pb < pa => Some(b.clone()),
+            (None, Some(_)) => Some(b.clone()),
+            _ => Some(a),
+        },
+        (a, b) => a.or_else(|| b.cloned()),
+    }
+}
+
+/// Rewrites wiki links (`[[id]]`, `[[id|label]]`, `[[id#heading]]`) whose id
+/// is a key of `ids`. Returns `None` when nothing changed.
+fn rewrite_links(contents: &str, ids: &HashMap<String, String>) -> Option<String> {
+    let mut out = String::with_capacity(contents.len());
+    let mut rest = contents;
+    let mut changed = false;
+    while let Some(open) = rest.find("[[") {
+        let (before, link) = rest.split_at(open + 2);
+        out.push_str(before);
+        let Some(close) = link.find("]]") else {
+            rest = link;
+            break;
+        };
+        let inner = &link[..close];
+        let id_end = inner.find(['|', '#']).unwrap_or(inner.len());
+        match ids.get(inner[..id_end].trim()) {
+            Some(new_id) => {
+                out.push_str(new_id);
+                out.push_str(&inner[id_end..]);
+                changed = true;
+            }
+            None => out.push_str(inner),
+        }
+        rest = &link[close..];
+    }
+    out.push_str(rest);
+    changed.then_some(out)
+}
+
+struct MergePlan<'a> {
+    target_rel: &'a str,
+    contents: &'a str,
+    sources: &'a [String],
+    renamed_ids: &'a HashMap<String, String>,
+    policy: CollisionPolicy,
+}
+
+/// Writes the merged target, archives the sources and relinks, recording each
+/// change. Returns where the sources went and which notes were relinked.
+fn apply_merge(
+    vault: &mut OpenVault,
+    plan: MergePlan<'_>,
+) -> VaultResult<(Vec<String>, Vec<String>)> {
+    let target_full = vault.root.join(plan.target_rel);
+    let before =
+        fs::read_to_string(&target_full).map_err(|e| VaultError::io(plan.target_rel, e))?;
+    write_atomic(&target_full, plan.contents)?;
+    vault.record_step(Step::Write {
+        path: plan.target_rel.to_string(),
+        before,
+        after: plan.contents.to_string(),
+    });
+    vault.index.refresh_note(&vault.root, plan.target_rel);
+
+    let mut archived = Vec::with_capacity(plan.sources.len());
+    for rel in plan.sources {
+        archived.push(archive_note(vault, rel, plan.policy)?);
+    }
+
+    let mut relinked = Vec::new();
+    if !plan.renamed_ids.is_empty() {
+        for entry in vault.index.list_notes(true) {
+            let rel = entry.relative_path;
+            let full = vault.root.join(&rel);
+            let Ok(before) = fs::read_to_string(&full) else {
+                continue;
+            };
+            // Rewritten in place so the rest of the file stays byte-for-byte.
+            let Some(after) = rewrite_links(&before, plan.renamed_ids) else {
+                continue;
+            };
+            write_atomic(&full, &after)?;
+            vault.record_step(Step::Write {
+                path: rel.clone(),
+                before,
+                after,
+            });
+            vault.index.refresh_note(&vault.root, &rel);
+            relinked.push(rel);
+        }
+    }
+    Ok((archived, relinked))
+}
+
+/// Folds `sources` into `target`: their bodies are appended in order, their
+/// projects and topics are added, and the earliest `created` wins. Sources are
+/// archived and links to them are pointed at the target.
+#[tauri::command]
+pub fn vault_merge_notes(
+    state: State<'_, VaultState>,
+    args: MergeNotesArgs,
+) -> VaultResult<MergeNotesResult> {
+    state.with_writable_vault(|vault| {
+        if args.sources.is_empty() {
+            return Err(VaultError::invalid_input("sources", "cannot be empty"));
+        }
+        // Relinking walks every note, including ones created outside the app.
+        vault.sync_index()?;
+        let (target_rel, mut merged) = read_parsed_note(vault, &args.target)?;
+        if target_rel.starts_with("notes/archive/") {
+            return Err(VaultError::invalid_path(
+                &target_rel,
+                "cannot merge into an archived note",
+            ));
+        }
+        let target_id = merged
+            .meta
+            .id()
+            .map(str::to_string)
+            .ok_or_else(|| VaultError::invalid_input("target", "note has no id"))?;
+
+        let mut seen = HashSet::from([target_rel.clone()]);
+        let mut sources = Vec::with_capacity(args.sources.len());
+        for source in &args.sources {
+            let (rel, note) = read_parsed_note(vault, source)?;
+            if !seen.insert(rel.clone()) {
+                return Err(VaultError::invalid_input(
+                    "sources",
+                    format!("{} is listed twice or is the target", rel),
+                ));
+            }
+            sources.push((rel, note));
+        }
+        // Archiving is the step most likely to be refused; find out before
+        // anything is written.
+        let policy = collision_policy(vault, None)?;
+        for (rel, _) in &sources {
+            let (rel, archived_rel) = archive_target(vault, rel)?;
+            if archived_rel != rel
+                && policy == CollisionPolicy::Fail
+                && vault.root.join(&archived_rel).exists()
+            {
+                return Err(VaultError::already_exists(&archived_rel));
+            }
+        }
+
+        let mut body = merged.body.trim_end().to_string();
+        let mut renamed_ids = HashMap::new();
+        for (_, source) in &sources {
+            push_unique(&mut merged.meta.projects, &source.meta.projects);
+            push_unique(&mut merged.meta.topics, &source.meta.topics);
+            merged.meta.created = earliest(merged.meta.created, source.meta.created.as_ref());
+            let text = source.body.trim();
+            if !text.is_empty() {
+                if !body.is_empty() {
+                    body.push_str("\n\n");
+                }
+                body.push_str(text);
+            }
+            if let Some(id) = source.meta.id().filter(|id| *id != target_id) {
+                renamed_ids.insert(id.to_string(), target_id.clone());
+            }
+        }
+        body.push('\n');
+        merged.meta.modified = Some(Utc::now().to_rfc3339());
+        merged.body = body;
+        let contents = merged.render().map_err(VaultError::internal)?;
+
+        let target_full = vault.root.join(&target_rel);
+        history::record(&vault.root, &target_full, &contents, true)?;
+        let sources: Vec<String> = sources.into_iter().map(|(rel, _)| rel).collect();
+        let plan = MergePlan {
+            target_rel: &target_rel,
+            contents: &contents,
+            sources: &sources,
+            renamed_ids: &renamed_ids,
+            policy,
+        };
+        let (archived, relinked) = match apply_merge(vault, plan) {
+            Ok(done) => done,
+            Err(e) => {
+                // Never leave the target merged with some sources still live.
+                journal::roll_back(vault);
+                return Err(e);
+            }
+        };
+
+        journal::commit(vault, "Merge notes");
+        let contents =
+            fs::read_to_string(&target_full).map_err(|e| VaultError::io(&target_rel, e))?;
+        Ok(MergeNotesResult {
+            relative_path: target_rel,
+            contents,
+            archived,
+            relinked,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids() -> HashMap<String, String> {
+        HashMap::from([("old".to_string(), "new".to_string())])
+    }
+
+    #[test]
+    fn rewrites_every_link_form() {
+        let contents = "See [[old]], [[old|the note]] and [[old#Part two]].";
+        assert_eq!(
+            rewrite_links(contents, &ids()).as_deref(),
+            Some("See [[new]], [[new|the note]] and [[new#Part two]].")
+        );
+    }
+
+    #[test]
+    fn leaves_other_links_alone() {
+        assert_eq!(
+            rewrite_links("[[older]] [[other|old]] [old](old)", &ids()),
+            None
+        );
+    }
+
+    #[test]
+    fn unclosed_links_are_kept_verbatim() {
+        assert_eq!(
+            rewrite_links("[[old]] then [[old", &ids()).as_deref(),
+            Some("[[new]] then [[old")
+        );
+    }
+
+    #[test]
+    fn copy_title_appends_to_the_first_heading() {
+        let (title, body) = copy_title("intro\n# Plan\ntext\n");
+        assert_eq!(title, "Plan (copy)");
+        assert_eq!(body, "intro\n# Plan (copy)\ntext\n");
+
+        let (title, body) = copy_title("text\n");
+        assert_eq!(title, "Untitled (copy)");
+        assert_eq!(body, "# Untitled (copy)\n\ntext\n");
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::Utc;
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+use crate::{
+    error::{VaultError, VaultResult},
+    vault::{write_atomic, VAULT_VERSION},
+};
+
+/// A single vault format upgrade. Steps must be idempotent: if the app dies
+/// after `apply` but before the new version is recorded, the step runs again on
+/// the next launch.
+struct Migration {
+    to_version: u32,
+    description: &'static str,
+    /// Vault-relative paths copied into `.homebase/backups/` before `apply` runs.
+    backup: &'static [&'static str],
+    apply: fn(&Path) -> VaultResult<()>,
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    to_version: 2,
+    description: "Drop absolute vaultPath from config/settings.json",
+    backup: &["config/settings.json"],
+    apply: drop_settings_vault_path,
+}];
+
+pub(crate) fn settings_path(vault_root: &Path) -> PathBuf {
+    vault_root.join("config/settings.json")
+}
+
+pub(crate) fn read_settings(vault_root: &Path) -> VaultResult<Value> {
+    let path = settings_path(vault_root);
+    let raw = fs::read_to_string(&path).map_err(|e| VaultError::io(&path, e))?;
+    let settings: Value =
+        serde_json::from_str(&raw).map_err(|e| VaultError::invalid_data(&path, e))?;
+    if !settings.is_object() {
+        return Err(VaultError::invalid_data(&path, "expected an object"));
+    }
+    Ok(settings)
+}
+
+pub(crate) fn write_settings(vault_root: &Path, settings: &Value) -> VaultResult<()> {
+    let raw = serde_json::to_string_pretty(settings).map_err(VaultError::internal)?;
+    write_atomic(&settings_path(vault_root), &raw)
+}
+
+/// Vaults created before versioning was tracked have no `version` key and are
+/// treated as version 1.
+fn stored_version(vault_root: &Path, settings: &Value) -> VaultResult<u32> {
+    match settings.get("version") {
+        None | Some(Value::Null) => Ok(1),
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| {
+                VaultError::invalid_data(
+                    settings_path(vault_root),
+                    format!("invalid version {}", v),
+                )
+            }),
+    }
+}
+
+fn copy_into_backup(vault_root: &Path, backup_root: &Path, rel: &str) -> VaultResult<()> {
+    let source = vault_root.join(rel);
+    if !source.exists() {
+        return Ok(());
+    }
+    for entry in WalkDir::new(&source).follow_links(false) {
+        let entry = entry.map_err(|e| {
+            let path = e.path().unwrap_or(&source).to_path_buf();
+            VaultError::io(&path, e.into())
+        })?;
+        let from = entry.path();
+        let rel_to_root = from
+            .strip_prefix(vault_root)
+            .map_err(|_| VaultError::OutsideVault {
+                path: from.to_string_lossy().to_string(),
+            })?;
+        let to = backup_root.join(rel_to_root);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&to).map_err(|e| VaultError::io(&to, e))?;
+        } else if entry.file_type().is_file() {
+            if let Some(parent) = to.parent() {
+                fs::create_dir_all(parent).map_err(|e| VaultError::io(parent, e))?;
+            }
+            fs::copy(from, &to).map_err(|e| VaultError::io(from, e))?;
+        }
+    }
+    Ok(())
+}
+
+fn backup_before(vault_root: &Path, from_version: u32, migration: &Migration) -> VaultResult<()> {
+    let backup_root = vault_root.join(".homebase/backups").join(format!(
+        "v{}-to-v{}-{}",
+        from_version,
+        migration.to_version,
+        Utc::now().format("%Y%m%dT%H%M%SZ")
+    ));
+    fs::create_dir_all(&backup_root).map_err(|e| VaultError::io(&backup_root, e))?;
+    for rel in migration.backup {
+        copy_into_backup(vault_root, &backup_root, rel)?;
+    }
+    Ok(())
+}
+
+/// Returns the stored vault version, failing for vaults written by a newer app.
+pub(crate) fn check_vault_version(vault_root: &Path) -> VaultResult<u32> {
+    let version = stored_version(vault_root, &read_settings(vault_root)?)?;
+    if version > VAULT_VERSION {
+        return Err(VaultError::UnsupportedVersion {
+            found: version,
+            supported: VAULT_VERSION,
+        });
+    }
+    Ok(version)
+}
+
+/// Brings the vault at `vault_root` up to `VAULT_VERSION` and returns the
+/// resulting version. Refuses to touch vaults written by a newer app.
+pub(crate) fn migrate_vault(vault_root: &Path) -> VaultResult<u32> {
+    let mut version = check_vault_version(vault_root)?;
+
+    for migration in MIGRATIONS {
+        if migration.to_version <= version {
+            continue;
+        }
+        backup_before(vault_root, version, migration)?;
+        (migration.apply)(vault_root).map_err(|e| {
+            VaultError::internal(format!(
+                "Migration to vault version {} ({}) failed: {}",
+                migration.to_version, migration.description, e
+            ))
+        })?;
+
+        let mut settings = read_settings(vault_root)?;
+        settings["version"] = Value::from(migration.to_version);
+        write_settings(vault_root, &settings)?;
+        version = migration.to_version;
+    }
+
+    Ok(version)
+}
+
+/// v2: the vault location is owned by the app-level registry, so a path baked
+/// into the vault goes stale as soon as the folder is moved or remounted.
+fn drop_settings_vault_path(vault_root: &Path) -> VaultResult<()> {
+    let mut settings = read_settings(vault_root)?;
+    if let Some(obj) = settings.as_object_mut() {
+        if obj.remove("vaultPath").is_some() {
+            write_settings(vault_root, &settings)?;
+        }
+    }
+    Ok(())
+}
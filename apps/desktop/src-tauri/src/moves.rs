@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File, OpenOptions},
+    io,
+    path::Path,
+};
+
+use crate::{
+    error::{VaultError, VaultResult},
+    vault::path_to_forward_slashes,
+};
+
+/// What to do when a note is moved onto a path that is already taken.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CollisionPolicy {
+    /// Refuse with `AlreadyExists`.
+    #[default]
+    Fail,
+    /// The incoming note gets a numbered name: `name-2.md`, `name-3.md`, ...
+    AutoSuffix,
+    /// The incoming note keeps its name and the note already there is moved
+    /// aside to a numbered name.
+    KeepBoth,
+}
+
+/// First free sibling of `rel` named `<stem><suffix(n)><ext>` for n = 1, 2, ...
+pub(crate) fn free_variant(vault_root: &Path, rel: &str, suffix: impl Fn(u32) -> String) -> String {
+    let path = Path::new(rel);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    let parent = path
+        .parent()
+        .map(path_to_forward_slashes)
+        .unwrap_or_default();
+    (1..)
+        .map(|n| format!("{}/{}{}{}", parent, stem, suffix(n), ext))
+        .find(|candidate| !vault_root.join(candidate).exists())
+        .unwrap_or_default()
+}
+
+/// `name-2.md`, `name-3.md`, ...
+pub(crate) fn numbered_variant(vault_root: &Path, rel: &str) -> String {
+    free_variant(vault_root, rel, |n| format!("-{}", n + 1))
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+fn copy_then_delete(from: &Path, to: &Path) -> VaultResult<()> {
+    let copy = || -> io::Result<()> {
+        let mut source = File::open(from)?;
+        let mut dest = OpenOptions::new().write(true).create_new(true).open(to)?;
+        let copied = io::copy(&mut source, &mut dest)?;
+        dest.sync_all()?;
+        if copied != source.metadata()?.len() {
+            return Err(io::Error::other("short copy"));
+        }
+        if let Some(parent) = to.parent() {
+            sync_dir(parent)?;
+        }
+        Ok(())
+    };
+    if let Err(e) = copy() {
+        // Leave the source untouched and drop the partial copy, unless the
+        // destination was someone else's file to begin with.
+        if e.kind() != io::ErrorKind::AlreadyExists {
+            let _ = fs::remove_file(to);
+        }
+        return Err(VaultError::io(to, e));
+    }
+    fs::remove_file(from).map_err(|e| VaultError::io(from, e))
+}
+
+/// Moves a file without ever replacing an existing destination. A hard link
+/// gives an atomic no-clobber move on the same filesystem; across devices (or
+/// where links are unsupported) the file is copied, fsynced and verified
+/// before the source is removed.
+pub(crate) fn move_file(from: &Path, to: &Path) -> VaultResult<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|e| VaultError::io(parent, e))?;
+    }
+    match fs::hard_link(from, to) {
+        Ok(()) => fs::remove_file(from).map_err(|e| VaultError::io(from, e)),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Err(VaultError::already_exists(to)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Err(VaultError::io(from, e)),
+        Err(_) => copy_then_delete(from, to),
+    }
+}
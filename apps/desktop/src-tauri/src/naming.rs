@@ -0,0 +1,290 @@
+use chrono::{DateTime, Local, NaiveDate};
+use std::{fs, ops::Range, path::Path};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    error::{VaultError, VaultResult},
+    frontmatter::Note,
+    journal::{self, Step},
+    moves::{move_file, numbered_variant},
+    periodic::PeriodKind,
+    settings,
+    state::{OpenVault, VaultState},
+    vault::{path_to_forward_slashes, slugify_or, validate_relative_path},
+};
+
+/// Keeps generated file names readable in a file browser.
+const MAX_SLUG_LEN: usize = 60;
+
+/// The 8-character suffix that ties a file name to its note id.
+pub(crate) fn id_short(id: &str) -> String {
+    let short: String = id
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .take(8)
+        .collect();
+    if short.is_empty() {
+        Uuid::new_v4().simple().to_string()[..8].to_string()
+    } else {
+        short
+    }
+}
+
+pub(crate) fn note_slug(title: &str) -> String {
+    let slug = slugify_or(title, "untitled");
+    if slug.len() <= MAX_SLUG_LEN {
+        return slug;
+    }
+    slug[..MAX_SLUG_LEN].trim_end_matches('-').to_string()
+}
+
+/// An ATX heading; offsets are bytes into the body it was found in.
+pub(crate) struct Heading {
+    /// Start of the heading's line.
+    pub line_start: usize,
+    pub level: usize,
+    pub text: Range<usize>,
+}
+
+/// Non-empty ATX headings outside fenced code blocks, in order.
+pub(crate) fn headings(body: &str) -> Vec<Heading> {
+    let mut found = Vec::new();
+    let mut in_fence = false;
+    let mut offset = 0;
+    for line in body.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if !(1..=6).contains(&level) {
+            continue;
+        }
+        let rest = &trimmed[level..];
+        if !rest.trim_end().is_empty() && !rest.starts_with([' ', '\t']) {
+            continue;
+        }
+        let text = rest.trim().trim_end_matches('#').trim();
+        if !text.is_empty() {
+            let text_start = line_start
+                + (line.len() - trimmed.len())
+                + level
+                + (rest.len() - rest.trim_start().len());
+            found.push(Heading {
+                line_start,
+                level,
+                text: text_start..text_start + text.len(),
+            });
+        }
+    }
+    found
+}
+
+/// Byte range of the first heading's text.
+pub(crate) fn first_heading_range(body: &str) -> Option<Range<usize>> {
+    headings(body).into_iter().next().map(|h| h.text)
+}
+
+/// Text of the first ATX heading outside fenced code blocks.
+pub(crate) fn first_heading(body: &str) -> Option<&str> {
+    first_heading_range(body).map(|range| &body[range])
+}
+
+/// Folder for notes derived from the one at `rel` (copies, split-off parts):
+/// its own, except that the date-managed periodic folders and the archive
+/// hand them to the inbox.
+pub(crate) fn derived_note_dir(rel: &str) -> String {
+    let parent = Path::new(rel)
+        .parent()
+        .map(path_to_forward_slashes)
+        .unwrap_or_default();
+    let date_managed = PeriodKind::from_relative_path(rel).is_some();
+    if date_managed || parent.starts_with("notes/archive") {
+        "notes/inbox".to_string()
+    } else {
+        parent
+    }
+}
+
+/// A free `dir/YYYY-MM-DD-<slug>-<idshort>.md` path for a new note.
+pub(crate) fn new_note_path(vault_root: &Path, dir: &str, title: &str, id: &str) -> String {
+    let date = Local::now().format("%Y-%m-%d");
+    let rel = format!("{}/{}-{}-{}.md", dir, date, note_slug(title), id_short(id));
+    if vault_root.join(&rel).exists() {
+        numbered_variant(vault_root, &rel)
+    } else {
+        rel
+    }
+}
+
+/// Splits `YYYY-MM-DD-<slug>-<idshort>.md` into its date and id suffix.
+fn parse_note_file_name(file_name: &str) -> Option<(String, String)> {
+    let stem = file_name.strip_suffix(".md")?;
+    let date = stem.get(..10)?;
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    let rest = stem.get(10..)?.strip_prefix('-')?;
+    let id = rest.rsplit_once('-').map_or(rest, |(_, id)| id);
+    let is_id = id.len() == 8 && id.chars().all(|c| c.is_ascii_alphanumeric());
+    is_id.then(|| (date.to_string(), id.to_string()))
+}
+
+/// The file name the note at `rel` should have given its first heading, or
+/// `None` when it has no heading. Periodic notes are named by date and never
+/// renamed.
+fn title_file_name(rel: &str, contents: &str) -> Option<String> {
+    if PeriodKind::from_relative_path(rel).is_some() {
+        return None;
+    }
+    let note = Note::parse(contents).unwrap_or_else(|_| Note::new(Default::default(), contents));
+    let title = first_heading(&note.body)?;
+    let file_name = Path::new(rel).file_name()?.to_string_lossy();
+    let (date, id) = parse_note_file_name(&file_name).unwrap_or_else(|| {
+        // Files named outside Homebase: take what we can from the frontmatter.
+        let date = note
+            .meta
+            .created
+            .as_deref()
+            .and_then(|c| DateTime::parse_from_rfc3339(c).ok())
+            .map(|c| c.with_timezone(&Local))
+            .unwrap_or_else(Local::now)
+            .format("%Y-%m-%d")
+            .to_string();
+        (date, id_short(note.meta.id().unwrap_or_default()))
+    });
+    Some(format!("{}-{}-{}.md", date, note_slug(title), id))
+}
+
+/// Whether `rel` is `target_rel` or one of its `numbered_variant`s
+/// (`name-2.md`, `name-3.md`, ...).
+fn is_variant_of(rel: &str, target_rel: &str) -> bool {
+    if rel == target_rel {
+        return true;
+    }
+    let (rel, target) = (Path::new(rel), Path::new(target_rel));
+    if rel.parent() != target.parent() || rel.extension() != target.extension() {
+        return false;
+    }
+    let (Some(stem), Some(target_stem)) = (
+        rel.file_stem().and_then(|s| s.to_str()),
+        target.file_stem().and_then(|s| s.to_str()),
+    ) else {
+        return false;
+    };
+    stem.strip_prefix(target_stem)
+        .and_then(|rest| rest.strip_prefix('-'))
+        .and_then(|n| n.parse::<u32>().ok())
+        .is_some_and(|n| n >= 2)
+}
+
+/// Renames the note to `file_name` in place and returns its new path. A note
+/// already at a numbered variant of that name stays put, so two notes whose
+/// titles derive the same name don't trade places on every save.
+fn rename_to(vault: &mut OpenVault, rel: &str, file_name: &str) -> VaultResult<String> {
+    let parent = Path::new(rel)
+        .parent()
+        .map(path_to_forward_slashes)
+        .unwrap_or_default();
+    let mut target_rel = format!("{}/{}", parent, file_name);
+    if is_variant_of(rel, &target_rel) {
+        return Ok(rel.to_string());
+    }
+    if vault.root.join(&target_rel).exists() {
+        target_rel = numbered_variant(&vault.root, &target_rel);
+    }
+    move_file(&vault.root.join(rel), &vault.root.join(&target_rel))?;
+    vault.record_step(Step::Move {
+        from: rel.to_string(),
+        to: target_rel.clone(),
+    });
+    vault.index.move_note(&vault.root, rel, &target_rel);
+    Ok(target_rel)
+}
+
+/// Called after every note write: follows the title when the vault has
+/// `autoRenameNotes` turned on. Returns the note's (possibly new) path.
+pub(crate) fn auto_rename(vault: &mut OpenVault, rel: &str, contents: &str) -> VaultResult<String> {
+    // Same scope as `vault_rename_note`: writes to config or other files
+    // outside `notes/` keep their names.
+    if !rel.starts_with("notes/") || !rel.ends_with(".md") {
+        return Ok(rel.to_string());
+    }
+    if !settings::load(&vault.root)?.auto_rename_notes {
+        return Ok(rel.to_string());
+    }
+    match title_file_name(rel, contents) {
+        Some(file_name) => rename_to(vault, rel, &file_name),
+        None => Ok(rel.to_string()),
+    }
+}
+
+#[tauri::command]
+pub fn vault_rename_note(
+    state: State<'_, VaultState>,
+    relative_path: String,
+) -> VaultResult<String> {
+    state.with_writable_vault(|vault| {
+        let rel = path_to_forward_slashes(&validate_relative_path(&relative_path)?);
+        if !rel.starts_with("notes/") || !rel.ends_with(".md") {
+            return Err(VaultError::invalid_path(
+                &rel,
+                "must be a note under notes/",
+            ));
+        }
+        if PeriodKind::from_relative_path(&rel).is_some() {
+            return Err(VaultError::invalid_path(
+                &rel,
+                "periodic notes are named by date",
+            ));
+        }
+        let full = vault.root.join(&rel);
+        let contents = fs::read_to_string(&full).map_err(|e| VaultError::io(&rel, e))?;
+        let file_name = title_file_name(&rel, &contents).ok_or_else(|| {
+            VaultError::invalid_input("title", "note has no heading to name the file after")
+        })?;
+        let target_rel = rename_to(vault, &rel, &file_name)?;
+        journal::commit(vault, "Rename note");
+        Ok(target_rel)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbered_variants_count_as_the_derived_name() {
+        let target = "notes/inbox/2024-05-01-plan-abcd1234.md";
+        assert!(is_variant_of(target, target));
+        assert!(is_variant_of(
+            "notes/inbox/2024-05-01-plan-abcd1234-2.md",
+            target
+        ));
+        assert!(is_variant_of(
+            "notes/inbox/2024-05-01-plan-abcd1234-17.md",
+            target
+        ));
+        assert!(!is_variant_of(
+            "notes/inbox/2024-05-01-plan-abcd1234-1.md",
+            target
+        ));
+        assert!(!is_variant_of(
+            "notes/inbox/2024-05-01-plan-abcd1234-x.md",
+            target
+        ));
+        assert!(!is_variant_of(
+            "notes/folders/2024-05-01-plan-abcd1234-2.md",
+            target
+        ));
+        assert!(!is_variant_of(
+            "notes/inbox/2024-05-01-plan-abcd12345.md",
+            target
+        ));
+    }
+}
@@ -0,0 +1,491 @@
+use chrono::{Datelike, Duration, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::{
+    error::{VaultError, VaultResult},
+    frontmatter::{Note, NoteMeta},
+    settings::{self, VaultSettings},
+    state::{OpenVault, VaultState},
+    templates::{self, render_template, TemplateContext},
+    vault::write_atomic,
+};
+
+/// Periodic notes live in one folder per kind, named by their period key:
+/// `notes/daily/2025-03-14.md`, `notes/weekly/2025-W11.md`,
+/// `notes/monthly/2025-03.md`, `notes/quarterly/2025-Q1.md`,
+/// `notes/yearly/2025.md`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PeriodKind {
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl PeriodKind {
+    pub(crate) const ALL: [PeriodKind; 5] = [
+        PeriodKind::Day,
+        PeriodKind::Week,
+        PeriodKind::Month,
+        PeriodKind::Quarter,
+        PeriodKind::Year,
+    ];
+
+    /// Also the prefix of the notes' ids, e.g. `weekly-2025-W11`.
+    fn name(self) -> &'static str {
+        match self {
+            PeriodKind::Day => "daily",
+            PeriodKind::Week => "weekly",
+            PeriodKind::Month => "monthly",
+            PeriodKind::Quarter => "quarterly",
+            PeriodKind::Year => "yearly",
+        }
+    }
+
+    pub(crate) fn key_format(self) -> &'static str {
+        match self {
+            PeriodKind::Day => "YYYY-MM-DD",
+            PeriodKind::Week => "YYYY-Www",
+            PeriodKind::Month => "YYYY-MM",
+            PeriodKind::Quarter => "YYYY-Qn",
+            PeriodKind::Year => "YYYY",
+        }
+    }
+
+    pub(crate) fn folder(self) -> String {
+        format!("notes/{}", self.name())
+    }
+
+    /// The kind whose folder holds `rel`, directly.
+    pub(crate) fn from_relative_path(rel: &str) -> Option<PeriodKind> {
+        Self::ALL.into_iter().find(|kind| {
+            rel.strip_prefix(&kind.folder())
+                .and_then(|rest| rest.strip_prefix('/'))
+                .is_some_and(|file| !file.contains('/'))
+        })
+    }
+
+    fn template(self, settings: &VaultSettings) -> Option<&String> {
+        match self {
+            PeriodKind::Day => settings.daily_template.as_ref(),
+            PeriodKind::Week => settings.weekly_template.as_ref(),
+            PeriodKind::Month => settings.monthly_template.as_ref(),
+            PeriodKind::Quarter => settings.quarterly_template.as_ref(),
+            PeriodKind::Year => settings.yearly_template.as_ref(),
+        }
+    }
+}
+
+/// A calendar period, identified by its kind and first day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Period {
+    pub kind: PeriodKind,
+    pub start: NaiveDate,
+}
+
+impl Period {
+    pub(crate) fn containing(kind: PeriodKind, date: NaiveDate) -> Option<Period> {
+        let start = match kind {
+            PeriodKind::Day => Some(date),
+            PeriodKind::Week => date.checked_sub_signed(Duration::days(i64::from(
+                date.weekday().num_days_from_monday(),
+            ))),
+            PeriodKind::Month => date.with_day(1),
+            PeriodKind::Quarter => {
+                NaiveDate::from_ymd_opt(date.year(), date.month0() / 3 * 3 + 1, 1)
+            }
+            PeriodKind::Year => NaiveDate::from_ymd_opt(date.year(), 1, 1),
+        }?;
+        Some(Period { kind, start })
+    }
+
+    /// Parses a period key. Only real calendar dates in the canonical form
+    /// are accepted: `2025-02-31`, `2025-W54` and `2025-3` are all rejected.
+    pub(crate) fn parse(kind: PeriodKind, key: &str) -> Option<Period> {
+        let date = match kind {
+            PeriodKind::Day => NaiveDate::parse_from_str(key, "%Y-%m-%d").ok()?,
+            PeriodKind::Week => {
+                let (year, week) = key.split_once("-W")?;
+                NaiveDate::from_isoywd_opt(
+                    year.parse().ok()?,
+                    week.parse().ok()?,
+                    chrono::Weekday::Mon,
+                )?
+            }
+            PeriodKind::Month => {
+                NaiveDate::parse_from_str(&format!("{}-01", key), "%Y-%m-%d").ok()?
+            }
+            PeriodKind::Quarter => {
+                let (year, quarter) = key.split_once("-Q")?;
+                let quarter: u32 = quarter.parse().ok()?;
+                if !(1..=4).contains(&quarter) {
+                    return None;
+                }
+                NaiveDate::from_ymd_opt(year.parse().ok()?, (quarter - 1) * 3 + 1, 1)?
+            }
+            PeriodKind::Year => NaiveDate::from_ymd_opt(key.parse().ok()?, 1, 1)?,
+        };
+        Period::containing(kind, date).filter(|period| period.key() == key)
+    }
+
+    pub(crate) fn from_relative_path(rel: &str) -> Option<Period> {
+        let kind = PeriodKind::from_relative_path(rel)?;
+        let key = rel.rsplit('/').next()?.strip_suffix(".md")?;
+        Period::parse(kind, key)
+    }
+
+    pub(crate) fn key(&self) -> String {
+        match self.kind {
+            PeriodKind::Day => self.start.format("%Y-%m-%d").to_string(),
+            PeriodKind::Week => {
+                let week = self.start.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            PeriodKind::Month => self.start.format("%Y-%m").to_string(),
+            PeriodKind::Quarter => {
+                format!("{}-Q{}", self.start.year(), self.start.month0() / 3 + 1)
+            }
+            PeriodKind::Year => self.start.format("%Y").to_string(),
+        }
+    }
+
+    /// Frontmatter for a new note of this period.
+    pub(crate) fn meta(&self, created: &str) -> NoteMeta {
+        let key = self.key();
+        let mut meta = NoteMeta::new(format!("{}-{}", self.kind.name(), key), created);
+        meta.user_placed = Some(true);
+        if self.kind == PeriodKind::Day {
+            meta.daily_date = Some(key);
+        }
+        meta
+    }
+
+    pub(crate) fn relative_path(&self) -> String {
+        format!("{}/{}.md", self.kind.folder(), self.key())
+    }
+
+    /// The period `steps` periods away (negative for earlier ones).
+    pub(crate) fn offset(&self, steps: i32) -> Option<Period> {
+        let months = match self.kind {
+            PeriodKind::Day => return self.shift_days(i64::from(steps)),
+            PeriodKind::Week => return self.shift_days(i64::from(steps) * 7),
+            PeriodKind::Month => 1,
+            PeriodKind::Quarter => 3,
+            PeriodKind::Year => 12,
+        };
+        let months = Months::new(steps.unsigned_abs().checked_mul(months)?);
+        let start = if steps >= 0 {
+            self.start.checked_add_months(months)
+        } else {
+            self.start.checked_sub_months(months)
+        }?;
+        Some(Period { start, ..*self })
+    }
+
+    fn shift_days(&self, days: i64) -> Option<Period> {
+        let start = self.start.checked_add_signed(Duration::days(days))?;
+        Some(Period { start, ..*self })
+    }
+
+    /// Last day of the period, inclusive.
+    pub(crate) fn end(&self) -> NaiveDate {
+        self.offset(1)
+            .and_then(|next| next.start.pred_opt())
+            .unwrap_or(NaiveDate::MAX)
+    }
+}
+
+fn parse_period(kind: PeriodKind, key: &str) -> VaultResult<Period> {
+    Period::parse(kind, key.trim()).ok_or_else(|| {
+        VaultError::invalid_input(
+            "key",
+            format!("must be a valid {} period", kind.key_format()),
+        )
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodInfo {
+    pub kind: PeriodKind,
+    pub key: String,
+    pub start: String,
+    pub end: String,
+    pub relative_path: String,
+    /// Whether the period's note has been created.
+    pub exists: bool,
+}
+
+impl PeriodInfo {
+    fn new(vault: &OpenVault, period: Period) -> Self {
+        let relative_path = period.relative_path();
+        PeriodInfo {
+            kind: period.kind,
+            key: period.key(),
+            start: period.start.format("%Y-%m-%d").to_string(),
+            end: period.end().format("%Y-%m-%d").to_string(),
+            exists: vault.root.join(&relative_path).is_file(),
+            relative_path,
+        }
+    }
+}
+
+/// Creates the period's note unless it exists, and returns its path. Explicit
+/// contents win over the kind's template; a template that has since been
+/// deleted falls back to a blank note rather than blocking the note.
+fn create_periodic_note(
+    vault: &mut OpenVault,
+    period: Period,
+    contents: Option<String>,
+) -> VaultResult<String> {
+    let rel_path = period.relative_path();
+    let full_path = vault.root.join(&rel_path);
+    if full_path.exists() {
+        return Ok(rel_path);
+    }
+
+    let key = period.key();
+    let meta = period.meta(&Utc::now().to_rfc3339());
+
+    let settings = settings::load(&vault.root)?;
+    let template = period
+        .kind
+        .template(&settings)
+        .filter(|name| contents.is_none() && templates::template_exists(&vault.root, name));
+    let contents = match template {
+        Some(name) => {
+            let ctx = TemplateContext {
+                date: period.start,
+                title: &key,
+                project: "",
+            };
+            render_template(&vault.root, name, &ctx, meta)?.0
+        }
+        None => Note::new(meta, contents.unwrap_or_default())
+            .render()
+            .map_err(VaultError::internal)?,
+    };
+
+    write_atomic(&full_path, &contents)?;
+    vault.index.refresh_note(&vault.root, &rel_path);
+    Ok(rel_path)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateDailyNoteArgs {
+    pub date: String,
+    pub contents: Option<String>,
+}
+
+#[tauri::command]
+pub fn vault_create_daily_note(
+    state: State<'_, VaultState>,
+    args: CreateDailyNoteArgs,
+) -> VaultResult<String> {
+    state.with_writable_vault(|vault| {
+        let period = Period::parse(PeriodKind::Day, args.date.trim()).ok_or_else(|| {
+            VaultError::invalid_input("date", "must be a valid date in YYYY-MM-DD format")
+        })?;
+        create_periodic_note(vault, period, args.contents)
+    })
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePeriodicNoteArgs {
+    pub kind: PeriodKind,
+    pub key: String,
+    pub contents: Option<String>,
+}
+
+#[tauri::command]
+pub fn vault_create_periodic_note(
+    state: State<'_, VaultState>,
+    args: CreatePeriodicNoteArgs,
+) -> VaultResult<String> {
+    state.with_writable_vault(|vault| {
+        let period = parse_period(args.kind, &args.key)?;
+        create_periodic_note(vault, period, args.contents)
+    })
+}
+
+/// The period containing `date` (YYYY-MM-DD), or today when omitted.
+#[tauri::command]
+pub fn vault_get_period(
+    state: State<'_, VaultState>,
+    kind: PeriodKind,
+    date: Option<String>,
+) -> VaultResult<PeriodInfo> {
+    state.with_vault(|vault| {
+        let date = match date {
+            Some(date) => parse_period(PeriodKind::Day, &date)?.start,
+            None => chrono::Local::now().date_naive(),
+        };
+        let period = Period::containing(kind, date)
+            .ok_or_else(|| VaultError::invalid_input("date", "is out of range"))?;
+        Ok(PeriodInfo::new(vault, period))
+    })
+}
+
+fn adjacent_period(
+    state: State<'_, VaultState>,
+    kind: PeriodKind,
+    key: &str,
+    steps: i32,
+) -> VaultResult<PeriodInfo> {
+    state.with_vault(|vault| {
+        let period = parse_period(kind, key)?
+            .offset(steps)
+            .ok_or_else(|| VaultError::invalid_input("key", "has no adjacent period"))?;
+        Ok(PeriodInfo::new(vault, period))
+    })
+}
+
+#[tauri::command]
+pub fn vault_previous_period(
+    state: State<'_, VaultState>,
+    kind: PeriodKind,
+    key: String,
+) -> VaultResult<PeriodInfo> {
+    adjacent_period(state, kind, &key, -1)
+}
+
+#[tauri::command]
+pub fn vault_next_period(
+    state: State<'_, VaultState>,
+    kind: PeriodKind,
+    key: String,
+) -> VaultResult<PeriodInfo> {
+    adjacent_period(state, kind, &key, 1)
+}
+
+/// Existing daily notes that fall within the period, oldest first.
+#[tauri::command]
+pub fn vault_list_daily_notes_in_period(
+    state: State<'_, VaultState>,
+    kind: PeriodKind,
+    key: String,
+) -> VaultResult<Vec<PeriodInfo>> {
+    state.with_vault(|vault| {
+        let period = parse_period(kind, &key)?;
+        let end = period.end();
+        let mut days: Vec<Period> = vault
+            .index
+            .list_notes(false)
+            .into_iter()
+            .filter_map(|entry| Period::from_relative_path(&entry.relative_path))
+            .filter(|day| {
+                day.kind == PeriodKind::Day && day.start >= period.start && day.start <= end
+            })
+            .collect();
+        days.sort_by_key(|day| day.start);
+        Ok(days
+            .into_iter()
+            .map(|day| PeriodInfo::new(vault, day))
+            .collect())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn period(kind: PeriodKind, key: &str) -> Period {
+        Period::parse(kind, key).unwrap_or_else(|| panic!("{} should parse", key))
+    }
+
+    #[test]
+    fn parses_canonical_keys() {
+        assert_eq!(
+            period(PeriodKind::Day, "2025-03-14").start,
+            date("2025-03-14")
+        );
+        // ISO week 1 of 2025 starts in 2024.
+        assert_eq!(
+            period(PeriodKind::Week, "2025-W01").start,
+            date("2024-12-30")
+        );
+        assert_eq!(
+            period(PeriodKind::Month, "2025-03").start,
+            date("2025-03-01")
+        );
+        assert_eq!(
+            period(PeriodKind::Quarter, "2025-Q3").start,
+            date("2025-07-01")
+        );
+        assert_eq!(period(PeriodKind::Year, "2025").start, date("2025-01-01"));
+    }
+
+    #[test]
+    fn rejects_non_canonical_keys() {
+        for (kind, key) in [
+            (PeriodKind::Day, "2025-02-31"),
+            (PeriodKind::Day, "2025-3-14"),
+            (PeriodKind::Week, "2025-W54"),
+            (PeriodKind::Week, "2025-W1"),
+            (PeriodKind::Month, "2025-3"),
+            (PeriodKind::Month, "2025-13"),
+            (PeriodKind::Quarter, "2025-Q5"),
+            (PeriodKind::Year, "25"),
+        ] {
+            assert_eq!(Period::parse(kind, key), None, "{}", key);
+        }
+    }
+
+    #[test]
+    fn offsets_across_boundaries() {
+        let key = |p: Option<Period>| p.unwrap().key();
+        assert_eq!(
+            key(period(PeriodKind::Day, "2024-02-28").offset(2)),
+            "2024-03-01"
+        );
+        assert_eq!(
+            key(period(PeriodKind::Week, "2024-W52").offset(1)),
+            "2025-W01"
+        );
+        assert_eq!(
+            key(period(PeriodKind::Month, "2025-01").offset(-1)),
+            "2024-12"
+        );
+        assert_eq!(
+            key(period(PeriodKind::Quarter, "2025-Q1").offset(-1)),
+            "2024-Q4"
+        );
+        assert_eq!(key(period(PeriodKind::Year, "2025").offset(3)), "2028");
+    }
+
+    #[test]
+    fn end_is_the_last_day() {
+        assert_eq!(
+            period(PeriodKind::Month, "2024-02").end(),
+            date("2024-02-29")
+        );
+        assert_eq!(
+            period(PeriodKind::Week, "2025-W01").end(),
+            date("2025-01-05")
+        );
+        assert_eq!(
+            period(PeriodKind::Quarter, "2025-Q4").end(),
+            date("2025-12-31")
+        );
+    }
+
+    #[test]
+    fn round_trips_through_relative_paths() {
+        let p = period(PeriodKind::Quarter, "2025-Q2");
+        assert_eq!(p.relative_path(), "notes/quarterly/2025-Q2.md");
+        assert_eq!(Period::from_relative_path(&p.relative_path()), Some(p));
+        assert_eq!(
+            Period::from_relative_path("notes/weekly/sub/2025-W01.md"),
+            None
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    error::{VaultError, VaultResult},
+    state::VaultState,
+    vault::{self, VaultInfo},
+};
+
+/// Matches the bundle identifier in `tauri.conf.json`, so the registry lives in
+/// the same directory Tauri reports as the app config dir.
+const APP_IDENTIFIER: &str = "com.artmashanin.homebase";
+const REGISTRY_FILE: &str = "vaults.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultEntry {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    pub added_at: String,
+    pub last_opened_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultRegistry {
+    pub active_vault_id: Option<String>,
+    pub vaults: Vec<VaultEntry>,
+}
+
+impl VaultRegistry {
+    fn active(&self) -> Option<&VaultEntry> {
+        let id = self.active_vault_id.as_deref()?;
+        self.vaults.iter().find(|v| v.id == id)
+    }
+
+    fn find_by_path(&self, path: &Path) -> Option<&VaultEntry> {
+        self.vaults.iter().find(|v| Path::new(&v.path) == path)
+    }
+}
+
+fn app_config_dir() -> VaultResult<PathBuf> {
+    let config = dirs::config_dir()
+        .ok_or_else(|| VaultError::internal("Failed to determine config directory"))?;
+    Ok(config.join(APP_IDENTIFIER))
+}
+
+fn registry_path() -> VaultResult<PathBuf> {
+    Ok(app_config_dir()?.join(REGISTRY_FILE))
+}
+
+fn default_vault_root() -> VaultResult<PathBuf> {
+    let home = dirs::home_dir()
+        .ok_or_else(|| VaultError::internal("Failed to determine home directory"))?;
+    Ok(home.join("Homebase"))
+}
+
+fn new_entry(name: String, path: &Path) -> VaultEntry {
+    VaultEntry {
+        id: Uuid::new_v4().to_string(),
+        name,
+        path: path.to_string_lossy().to_string(),
+        added_at: Utc::now().to_rfc3339(),
+        last_opened_at: None,
+    }
+}
+
+fn default_name_for(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or_else(|| "Vault".to_string())
+}
+
+/// Loads the registry, seeding it with the default `~/Homebase` vault on first
+/// launch so existing installs keep working without any setup.
+fn load_registry() -> VaultResult<VaultRegistry> {
+    let path = registry_path()?;
+    let mut registry: VaultRegistry = if path.exists() {
+        let raw = fs::read_to_string(&path).map_err(|e| VaultError::io(&path, e))?;
+        serde_json::from_str(&raw).map_err(|e| VaultError::invalid_data(&path, e))?
+    } else {
+        VaultRegistry::default()
+    };
+
+    if registry.vaults.is_empty() {
+        let root = default_vault_root()?;
+        let entry = new_entry("Homebase".to_string(), &root);
+        registry.active_vault_id = Some(entry.id.clone());
+        registry.vaults.push(entry);
+        save_registry(&registry)?;
+    } else if registry.active().is_none() {
+        registry.active_vault_id = registry.vaults.first().map(|v| v.id.clone());
+        save_registry(&registry)?;
+    }
+
+    Ok(registry)
+}
+
+fn save_registry(registry: &VaultRegistry) -> VaultResult<()> {
+    let path = registry_path()?;
+    let raw = serde_json::to_string_pretty(registry).map_err(VaultError::internal)?;
+    vault::write_atomic(&path, &raw)
+}
+
+/// Resolves the root directory of the currently selected vault.
+pub(crate) fn active_vault_root() -> VaultResult<PathBuf> {
+    let registry = load_registry()?;
+    let entry = registry
+        .active()
+        .ok_or_else(|| VaultError::internal("No active vault"))?;
+    let root = PathBuf::from(&entry.path);
+    // Only the default vault is created on demand; anything else missing is most
+    // likely an unmounted drive, and silently creating a fresh vault there would
+    // hide the user's notes.
+    if !root.exists() && root != default_vault_root()? {
+        return Err(VaultError::VaultUnavailable {
+            path: entry.path.clone(),
+        });
+    }
+    Ok(root)
+}
+
+fn validate_vault_dir(path: &str) -> VaultResult<PathBuf> {
+    let trimmed = path.trim();
+    if trimmed.is_empty() {
+        return Err(VaultError::invalid_input("path", "cannot be empty"));
+    }
+    let root = PathBuf::from(trimmed);
+    if !root.is_absolute() {
+        return Err(VaultError::invalid_path(trimmed, "must be absolute"));
+    }
+    Ok(root)
+}
+
+fn activate(state: &VaultState, registry: &mut VaultRegistry, id: &str) -> VaultResult<VaultInfo> {
+    let entry = registry
+        .vaults
+        .iter_mut()
+        .find(|v| v.id == id)
+        .ok_or_else(|| VaultError::IdNotFound {
+            kind: "vault",
+            id: id.to_string(),
+        })?;
+    // Only remember the vault as active once it has opened, so a vault that
+    // cannot be opened does not replace the working one on the next launch.
+    let info = state.open(PathBuf::from(&entry.path))?;
+    entry.last_opened_at = Some(Utc::now().to_rfc3339());
+    registry.active_vault_id = Some(id.to_string());
+    save_registry(registry)?;
+    Ok(info)
+}
+
+#[tauri::command]
+pub fn vault_list_vaults() -> VaultResult<VaultRegistry> {
+    load_registry()
+}
+
+#[tauri::command]
+pub fn vault_add_vault(path: String, name: Option<String>) -> VaultResult<VaultEntry> {
+    let root = validate_vault_dir(&path)?;
+    let mut registry = load_registry()?;
+    if registry.find_by_path(&root).is_some() {
+        return Err(VaultError::already_exists(&root));
+    }
+
+    fs::create_dir_all(&root).map_err(|e| VaultError::io(&root, e))?;
+
+    let name = name
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| default_name_for(&root));
+    let entry = new_entry(name, &root);
+    registry.vaults.push(entry.clone());
+    save_registry(&registry)?;
+    Ok(entry)
+}
+
+#[tauri::command]
+pub fn vault_remove_vault(state: State<'_, VaultState>, id: String) -> VaultResult<VaultRegistry> {
+    let mut registry = load_registry()?;
+    let before = registry.vaults.len();
+    registry.vaults.retain(|v| v.id != id);
+    if registry.vaults.len() == before {
+        return Err(VaultError::IdNotFound { kind: "vault", id });
+    }
+    let removed_active = registry.active_vault_id.as_deref() == Some(id.as_str());
+    if removed_active {
+        registry.active_vault_id = registry.vaults.first().map(|v| v.id.clone());
+    }
+    save_registry(&registry)?;
+    if removed_active {
+        // Release the removed vault's lock; the next command opens whichever
+        // vault is active now.
+        state.close();
+    }
+    // Removing the last vault falls back to the default one on the next load.
+    load_registry()
+}
+
+#[tauri::command]
+pub fn vault_open_vault(state: State<'_, VaultState>, path: String) -> VaultResult<VaultInfo> {
+    let root = validate_vault_dir(&path)?;
+    if !root.is_dir() {
+        return Err(VaultError::not_found(&root));
+    }
+
+    let mut registry = load_registry()?;
+    let id = match registry.find_by_path(&root) {
+        Some(entry) => entry.id.clone(),
+        None => {
+            let entry = new_entry(default_name_for(&root), &root);
+            let id = entry.id.clone();
+            registry.vaults.push(entry);
+            id
+        }
+    };
+    activate(&state, &mut registry, &id)
+}
+
+#[tauri::command]
+pub fn vault_switch_vault(state: State<'_, VaultState>, id: String) -> VaultResult<VaultInfo> {
+    let mut registry = load_registry()?;
+    activate(&state, &mut registry, &id)
+}
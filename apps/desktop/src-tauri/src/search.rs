@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::{
+    error::{VaultError, VaultResult},
+    state::VaultState,
+};
+
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 100;
+/// Private-use characters that wrap matches in the text SQLite hands back,
+/// turned into ranges before it leaves the backend.
+const MATCH_START: char = '\u{E001}';
+const MATCH_END: char = '\u{E002}';
+
+/// Builds an FTS5 expression from what the user typed: `"quoted text"` is a
+/// phrase, a trailing `*` makes a word a prefix, and every term must match.
+/// Terms are always quoted, so FTS5 operators in the input are just text.
+fn fts_query(input: &str) -> Option<String> {
+    let quote = |text: &str| format!("\"{}\"", text.replace('"', "\"\""));
+    let searchable = |text: &str| text.chars().any(char::is_alphanumeric);
+    let mut terms = Vec::new();
+    let mut rest = input.trim_start();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('"') {
+            let (phrase, next) = after.split_once('"').unwrap_or((after, ""));
+            if searchable(phrase) {
+                terms.push(quote(phrase));
+            }
+            rest = next;
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == '"')
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            let (word, prefix) = match word.strip_suffix('*') {
+                Some(word) => (word, "*"),
+                None => (word, ""),
+            };
+            if searchable(word) {
+                terms.push(quote(word) + prefix);
+            }
+            rest = &rest[end..];
+        }
+        rest = rest.trim_start();
+    }
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// A highlighted span, in UTF-16 code units (the way JavaScript indexes
+/// strings).
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TextRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Removes the match markers from `marked`, returning the plain text and where
+/// the marked spans were.
+fn take_highlights(marked: &str) -> (String, Vec<TextRange>) {
+    let mut text = String::with_capacity(marked.len());
+    let mut ranges = Vec::new();
+    let mut offset = 0;
+    let mut start = None;
+    for c in marked.chars() {
+        match c {
+            MATCH_START => start = Some(offset),
+            MATCH_END => {
+                if let Some(start) = start.take() {
+                    ranges.push(TextRange { start, end: offset });
+                }
+            }
+            c => {
+                text.push(c);
+                offset += c.len_utf16();
+            }
+        }
+    }
+    (text, ranges)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchArgs {
+    pub query: String,
+    /// Page size; defaults to 20, at most 100.
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    #[serde(default)]
+    pub include_archived: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub relative_path: String,
+    pub kind: String,
+    pub title: String,
+    pub title_highlights: Vec<TextRange>,
+    /// A few words around the best match, from whichever field matched.
+    pub snippet: String,
+    pub snippet_highlights: Vec<TextRange>,
+    /// Higher is better; only meaningful relative to other hits.
+    pub score: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResults {
+    pub total: usize,
+    pub hits: Vec<SearchHit>,
+    /// Offset of the next page, if there is one.
+    pub next_offset: Option<usize>,
+}
+
+/// Full-text search over titles, bodies, frontmatter and task lines, best
+/// matches first.
+#[tauri::command]
+pub fn vault_search(state: State<'_, VaultState>, args: SearchArgs) -> VaultResult<SearchResults> {
+    state.with_vault(|vault| {
+        let Some(query) = fts_query(&args.query) else {
+            return Ok(SearchResults {
+                total: 0,
+                hits: Vec::new(),
+                next_offset: None,
+            });
+        };
+        let search = vault
+            .index
+            .search_index()
+            .ok_or_else(|| VaultError::internal("Search index is unavailable"))?;
+        let limit = args.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let offset = args.offset.unwrap_or(0);
+        let (total, raw) = search
+            .search(
+                &query,
+                args.include_archived,
+                (MATCH_START, MATCH_END),
+                limit,
+                offset,
+            )
+            .map_err(VaultError::internal)?;
+
+        let next_offset = offset + raw.len();
+        let hits = raw
+            .into_iter()
+            .map(|hit| {
+                let (title, title_highlights) = take_highlights(&hit.title);
+                let (snippet, snippet_highlights) = take_highlights(&hit.snippet);
+                SearchHit {
+                    relative_path: hit.relative_path,
+                    kind: hit.kind,
+                    title,
+                    title_highlights,
+                    snippet,
+                    snippet_highlights,
+                    score: hit.score,
+                }
+            })
+            .collect();
+        Ok(SearchResults {
+            total,
+            hits,
+            next_offset: (next_offset < total).then_some(next_offset),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_every_term() {
+        assert_eq!(
+            fts_query("rust  notes").as_deref(),
+            Some("\"rust\" \"notes\"")
+        );
+        assert_eq!(fts_query("plan*").as_deref(), Some("\"plan\"*"));
+        assert_eq!(
+            fts_query("\"exact phrase\" more").as_deref(),
+            Some("\"exact phrase\" \"more\"")
+        );
+    }
+
+    #[test]
+    fn operators_and_quotes_are_plain_text() {
+        assert_eq!(
+            fts_query("a OR b NOT c").as_deref(),
+            Some("\"a\" \"OR\" \"b\" \"NOT\" \"c\"")
+        );
+        assert_eq!(fts_query("it\"s").as_deref(), Some("\"it\" \"s\""));
+        assert_eq!(
+            fts_query("\"unclosed phrase").as_deref(),
+            Some("\"unclosed phrase\"")
+        );
+    }
+
+    #[test]
+    fn nothing_searchable_is_none() {
+        assert_eq!(fts_query(""), None);
+        assert_eq!(fts_query("  * - \"\" "), None);
+    }
+
+    #[test]
+    fn highlights_are_utf16_ranges() {
+        let marked = format!(
+            "😀 {}café{} au {}lait{}",
+            MATCH_START, MATCH_END, MATCH_START, MATCH_END
+        );
+        let (text, ranges) = take_highlights(&marked);
+        assert_eq!(text, "😀 café au lait");
+        let spans: Vec<(usize, usize)> = ranges.iter().map(|r| (r.start, r.end)).collect();
+        // The emoji is two UTF-16 code units.
+        assert_eq!(spans, [(3, 7), (11, 15)]);
+    }
+}
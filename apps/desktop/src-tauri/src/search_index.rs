@@ -0,0 +1,313 @@
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Transaction};
+use serde_yaml::Value;
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{frontmatter::Note, index::NoteRecord, naming, vault::kind_from_relative_path};
+
+const INDEX_FILE: &str = ".homebase/index.sqlite";
+/// Stored in `PRAGMA user_version`. Bump it whenever the tables or what goes
+/// into them change; an index with any other version is dropped and rebuilt
+/// from the notes on disk.
+const SCHEMA_VERSION: i32 = 1;
+
+const SCHEMA: &str = "
+CREATE TABLE notes (
+    doc INTEGER PRIMARY KEY,
+    path TEXT NOT NULL UNIQUE,
+    mtime_ms INTEGER NOT NULL,
+    size INTEGER NOT NULL,
+    note_id TEXT,
+    kind TEXT NOT NULL,
+    title TEXT NOT NULL
+);
+CREATE VIRTUAL TABLE notes_fts USING fts5(
+    title, body, meta, tasks,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+";
+
+/// Full-text index of the vault's notes in `.homebase/index.sqlite`, one
+/// `notes` row per file and a `notes_fts` row sharing its `doc` as rowid.
+///
+/// It is a cache: every row carries the mtime and size of the file it was
+/// built from, so opening the vault brings it back in line with the disk, and
+/// failed updates only cost freshness until then.
+#[derive(Debug)]
+pub(crate) struct SearchIndex {
+    conn: Connection,
+    writable: bool,
+}
+
+/// Relative weights of the `title`, `body`, `meta` and `tasks` columns when
+/// ranking with bm25.
+const COLUMN_WEIGHTS: &str = "10.0, 1.0, 4.0, 2.0";
+
+/// One match, with the title and snippet text still carrying the caller's
+/// highlight markers.
+pub(crate) struct RawHit {
+    pub relative_path: String,
+    pub kind: String,
+    pub title: String,
+    pub snippet: String,
+    /// bm25, negated so that higher is better.
+    pub score: f64,
+}
+
+/// The columns indexed for one note.
+struct Document {
+    title: String,
+    body: String,
+    meta: String,
+    tasks: String,
+}
+
+/// The first heading, else the first non-blank line without its markdown
+/// markers, else the file name.
+fn note_title(rel: &str, body: &str) -> String {
+    if let Some(heading) = naming::first_heading(body) {
+        return heading.to_string();
+    }
+    let line = body
+        .lines()
+        .map(|line| {
+            line.trim_start_matches(['#', '>', '-', '*', '+', ' ', '\t'])
+                .trim()
+        })
+        .find(|line| !line.is_empty());
+    match line {
+        Some(line) => line.chars().take(80).collect(),
+        None => {
+            let name = rel.rsplit('/').next().unwrap_or(rel);
+            name.strip_suffix(".md").unwrap_or(name).to_string()
+        }
+    }
+}
+
+fn push_yaml_text(out: &mut Vec<String>, value: &Value) {
+    match value {
+        Value::String(s) => out.push(s.clone()),
+        Value::Number(n) => out.push(n.to_string()),
+        Value::Bool(b) => out.push(b.to_string()),
+        Value::Sequence(items) => items.iter().for_each(|item| push_yaml_text(out, item)),
+        Value::Mapping(map) => map.values().for_each(|item| push_yaml_text(out, item)),
+        Value::Tagged(tagged) => push_yaml_text(out, &tagged.value),
+        Value::Null => {}
+    }
+}
+
+/// The text after the checkbox of each `- [ ]` / `- [x]` line.
+fn task_lines(body: &str) -> String {
+    body.lines()
+        .filter_map(|line| {
+            let rest = line.trim_start().strip_prefix(['-', '*', '+'])?;
+            let rest = rest.strip_prefix([' ', '\t'])?.trim_start();
+            let rest = ["[ ]", "[x]", "[X]"]
+                .iter()
+                .find_map(|checkbox| rest.strip_prefix(checkbox))?;
+            rest.starts_with([' ', '\t']).then(|| rest.trim())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn document(rel: &str, contents: &str) -> Document {
+    let (meta, body) = match Note::parse(contents) {
+        Ok(note) => {
+            let mut meta = note.meta.projects.clone();
+            meta.extend(note.meta.topics.iter().cloned());
+            for value in note.meta.extras.values() {
+                push_yaml_text(&mut meta, value);
+            }
+            (meta.join("\n"), note.body)
+        }
+        Err(_) => (String::new(), contents.to_string()),
+    };
+    Document {
+        title: note_title(rel, &body),
+        tasks: task_lines(&body),
+        meta,
+        body,
+    }
+}
+
+impl SearchIndex {
+    /// Opens the index, creating it or rebuilding its tables if they are
+    /// missing or from another schema version. A read-only vault belongs to
+    /// another process, so its index is only read, and only if it is current.
+    /// `None` when there is no usable index; the vault works without one.
+    pub(crate) fn open(vault_root: &Path, writable: bool) -> Option<Self> {
+        let path = vault_root.join(INDEX_FILE);
+        let conn = if writable {
+            fs::create_dir_all(path.parent()?).ok()?;
+            Connection::open(&path).ok()?
+        } else {
+            Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY).ok()?
+        };
+        let version: i32 = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .ok()?;
+        if version != SCHEMA_VERSION {
+            if !writable {
+                return None;
+            }
+            conn.execute_batch(&format!(
+                "BEGIN;
+                 DROP TABLE IF EXISTS notes;
+                 DROP TABLE IF EXISTS notes_fts;
+                 {}
+                 PRAGMA user_version = {};
+                 COMMIT;",
+                SCHEMA, SCHEMA_VERSION
+            ))
+            .ok()?;
+        }
+        Some(SearchIndex { conn, writable })
+    }
+
+    /// Path -> (mtime in ms, size) of every indexed note, for reconciling.
+    pub(crate) fn stored(&self) -> rusqlite::Result<HashMap<String, (i64, u64)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, mtime_ms, size FROM notes")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?;
+        rows.collect()
+    }
+
+    /// Starts a transaction, so a reconcile touching many notes is a single
+    /// write. Dropping it uncommitted rolls back.
+    pub(crate) fn begin(&self) -> rusqlite::Result<Transaction<'_>> {
+        self.conn.unchecked_transaction()
+    }
+
+    pub(crate) fn upsert(
+        &self,
+        rel: &str,
+        record: &NoteRecord,
+        contents: &str,
+    ) -> rusqlite::Result<()> {
+        if !self.writable {
+            return Ok(());
+        }
+        self.remove(rel)?;
+        let doc = document(rel, contents);
+        self.conn.execute(
+            "INSERT INTO notes (path, mtime_ms, size, note_id, kind, title)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                rel,
+                record.mtime_ms,
+                record.size,
+                record.id,
+                kind_from_relative_path(rel),
+                doc.title
+            ],
+        )?;
+        self.conn.execute(
+            "INSERT INTO notes_fts (rowid, title, body, meta, tasks)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                self.conn.last_insert_rowid(),
+                doc.title,
+                doc.body,
+                doc.meta,
+                doc.tasks
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn remove(&self, rel: &str) -> rusqlite::Result<()> {
+        if !self.writable {
+            return Ok(());
+        }
+        let doc: Option<i64> = self
+            .conn
+            .query_row("SELECT doc FROM notes WHERE path = ?1", [rel], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        if let Some(doc) = doc {
+            self.conn
+                .execute("DELETE FROM notes_fts WHERE rowid = ?1", [doc])?;
+            self.conn
+                .execute("DELETE FROM notes WHERE doc = ?1", [doc])?;
+        }
+        Ok(())
+    }
+
+    /// Runs an FTS5 `MATCH` expression. Returns the total number of matches
+    /// and the page of them at `offset`, best first. Matches are wrapped in
+    /// `marks` in both the title and the snippet.
+    pub(crate) fn search(
+        &self,
+        fts_query: &str,
+        include_archived: bool,
+        marks: (char, char),
+        limit: usize,
+        offset: usize,
+    ) -> rusqlite::Result<(usize, Vec<RawHit>)> {
+        let total: i64 = self.conn.query_row(
+            "SELECT count(*) FROM notes_fts JOIN notes ON notes.doc = notes_fts.rowid
+             WHERE notes_fts MATCH ?1 AND (?2 OR notes.kind != 'archive')",
+            params![fts_query, include_archived],
+            |row| row.get(0),
+        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT notes.path, notes.kind, bm25(notes_fts, {}) AS rank,
+                    highlight(notes_fts, 0, ?3, ?4),
+                    snippet(notes_fts, -1, ?3, ?4, '…', 16)
+             FROM notes_fts JOIN notes ON notes.doc = notes_fts.rowid
+             WHERE notes_fts MATCH ?1 AND (?2 OR notes.kind != 'archive')
+             ORDER BY rank
+             LIMIT ?5 OFFSET ?6",
+            COLUMN_WEIGHTS
+        ))?;
+        let hits = stmt.query_map(
+            params![
+                fts_query,
+                include_archived,
+                marks.0.to_string(),
+                marks.1.to_string(),
+                limit,
+                offset
+            ],
+            |row| {
+                Ok(RawHit {
+                    relative_path: row.get(0)?,
+                    kind: row.get(1)?,
+                    score: -row.get::<_, f64>(2)?,
+                    title: row.get(3)?,
+                    snippet: row.get(4)?,
+                })
+            },
+        )?;
+        Ok((total as usize, hits.collect::<rusqlite::Result<_>>()?))
+    }
+
+    /// Re-keys the notes below directory `from` after it was renamed to `to`.
+    /// Their text is unchanged, so the full-text rows stay as they are.
+    pub(crate) fn rename_dir(&self, from: &str, to: &str) -> rusqlite::Result<()> {
+        if !self.writable {
+            return Ok(());
+        }
+        let prefix = format!("{}/", from);
+        let moved: Vec<(i64, String)> = {
+            let mut stmt = self
+                .conn
+                .prepare("SELECT doc, path FROM notes WHERE substr(path, 1, ?1) = ?2")?;
+            // substr() counts characters, not bytes.
+            let rows = stmt.query_map(params![prefix.chars().count(), prefix], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        for (doc, path) in moved {
+            let path = format!("{}{}", to, &path[from.len()..]);
+            self.conn.execute(
+                "UPDATE notes SET path = ?1, kind = ?2 WHERE doc = ?3",
+                params![path, kind_from_relative_path(&path), doc],
+            )?;
+        }
+        Ok(())
+    }
+}
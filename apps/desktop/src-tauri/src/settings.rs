@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use tauri::State;
+
+use crate::{
+    error::{VaultError, VaultResult},
+    migrations::{read_settings, settings_path, write_settings},
+    moves::CollisionPolicy,
+    state::VaultState,
+};
+
+fn default_trash_retention_days() -> u32 {
+    30
+}
+
+/// The user-tunable part of `config/settings.json`. Keys not listed here
+/// (`version`, `createdAt`, ...) are left untouched by updates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultSettings {
+    /// Days a trashed item is kept before it is purged; 0 keeps it forever.
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u32,
+    /// Default for note moves, archives and unarchives that land on a taken path.
+    #[serde(default)]
+    pub collision_policy: CollisionPolicy,
+    /// Rename note files after their first heading whenever they are saved.
+    #[serde(default)]
+    pub auto_rename_notes: bool,
+    /// Templates in `config/templates` that new periodic notes start from.
+    #[serde(default)]
+    pub daily_template: Option<String>,
+    #[serde(default)]
+    pub weekly_template: Option<String>,
+    #[serde(default)]
+    pub monthly_template: Option<String>,
+    #[serde(default)]
+    pub quarterly_template: Option<String>,
+    #[serde(default)]
+    pub yearly_template: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateSettingsArgs {
+    pub trash_retention_days: Option<u32>,
+    pub collision_policy: Option<CollisionPolicy>,
+    pub auto_rename_notes: Option<bool>,
+    /// For the templates, an empty string clears the setting.
+    pub daily_template: Option<String>,
+    pub weekly_template: Option<String>,
+    pub monthly_template: Option<String>,
+    pub quarterly_template: Option<String>,
+    pub yearly_template: Option<String>,
+}
+
+pub(crate) fn load(vault_root: &Path) -> VaultResult<VaultSettings> {
+    serde_json::from_value(read_settings(vault_root)?)
+        .map_err(|e| VaultError::invalid_data(settings_path(vault_root), e))
+}
+
+#[tauri::command]
+pub fn vault_get_settings(state: State<'_, VaultState>) -> VaultResult<VaultSettings> {
+    state.with_vault(|vault| load(&vault.root))
+}
+
+#[tauri::command]
+pub fn vault_update_settings(
+    state: State<'_, VaultState>,
+    args: UpdateSettingsArgs,
+) -> VaultResult<VaultSettings> {
+    state.with_writable_vault(|vault| {
+        let mut settings = read_settings(&vault.root)?;
+        let obj = settings.as_object_mut().ok_or_else(|| {
+            VaultError::invalid_data(settings_path(&vault.root), "expected an object")
+        })?;
+        if let Some(days) = args.trash_retention_days {
+            obj.insert("trashRetentionDays".to_string(), Value::from(days));
+        }
+        if let Some(policy) = args.collision_policy {
+            let value = serde_json::to_value(policy).map_err(VaultError::internal)?;
+            obj.insert("collisionPolicy".to_string(), value);
+        }
+        if let Some(auto_rename) = args.auto_rename_notes {
+            obj.insert("autoRenameNotes".to_string(), Value::from(auto_rename));
+        }
+        let templates = [
+            ("dailyTemplate", args.daily_template),
+            ("weeklyTemplate", args.weekly_template),
+            ("monthlyTemplate", args.monthly_template),
+            ("quarterlyTemplate", args.quarterly_template),
+            ("yearlyTemplate", args.yearly_template),
+        ];
+        for (key, template) in templates {
+            let Some(template) = template else {
+                continue;
+            };
+            let template = template.trim();
+            if template.is_empty() {
+                obj.remove(key);
+            } else {
+                obj.insert(key.to_string(), Value::from(template));
+            }
+        }
+        write_settings(&vault.root, &settings)?;
+        load(&vault.root)
+    })
+}
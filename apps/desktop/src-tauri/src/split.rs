@@ -0,0 +1,220 @@
+use chrono::Utc;
+use serde::Serialize;
+use std::fs;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    error::{VaultError, VaultResult},
+    frontmatter::{Note, NoteMeta},
+    history,
+    journal::{self, Step},
+    naming,
+    state::{OpenVault, VaultState},
+    vault::{read_parsed_note, write_atomic, CreateNoteResult},
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SplitNoteResult {
+    pub relative_path: String,
+    /// The original note, now an index of links to the parts.
+    pub contents: String,
+    pub parts: Vec<CreateNoteResult>,
+}
+
+/// A section that becomes its own note: its heading's text and everything
+/// from the heading line up to the next split point.
+struct Part<'a> {
+    title: &'a str,
+    body: &'a str,
+}
+
+/// Cuts `body` before every heading of `level` or shallower, once the first
+/// heading of exactly `level` has been seen. Text before that stays with the
+/// original note.
+fn split_body(body: &str, level: usize) -> (&str, Vec<Part<'_>>) {
+    let headings = naming::headings(body);
+    let Some(first) = headings.iter().position(|h| h.level == level) else {
+        return (body, Vec::new());
+    };
+    let cuts: Vec<_> = headings[first..]
+        .iter()
+        .filter(|h| h.level <= level)
+        .collect();
+    let parts = cuts
+        .iter()
+        .enumerate()
+        .map(|(i, h)| {
+            let end = cuts.get(i + 1).map_or(body.len(), |next| next.line_start);
+            Part {
+                title: &body[h.text.clone()],
+                body: &body[h.line_start..end],
+            }
+        })
+        .collect();
+    (&body[..headings[first].line_start], parts)
+}
+
+/// Writes the parts and rewrites the original as an index of links to them.
+/// Returns the original's new contents and the parts.
+fn apply_split(
+    vault: &mut OpenVault,
+    rel: &str,
+    mut note: Note,
+    level: usize,
+) -> VaultResult<(String, Vec<CreateNoteResult>)> {
+    let (preamble, sections) = split_body(&note.body, level);
+    if sections.is_empty() {
+        return Err(VaultError::invalid_input(
+            "level",
+            "note has no headings at that level",
+        ));
+    }
+
+    let now_iso = Utc::now().to_rfc3339();
+    let target_dir = naming::derived_note_dir(rel);
+    let mut parts = Vec::with_capacity(sections.len());
+    let mut links = Vec::with_capacity(sections.len());
+    for section in &sections {
+        let id = Uuid::new_v4().to_string();
+        let mut meta = NoteMeta::new(id.clone(), &now_iso);
+        meta.projects = note.meta.projects.clone();
+        meta.topics = note.meta.topics.clone();
+        meta.user_placed = Some(target_dir != "notes/inbox");
+        let contents = Note::new(meta, section.body.trim_end().to_string() + "\n")
+            .render()
+            .map_err(VaultError::internal)?;
+
+        let target_rel = naming::new_note_path(&vault.root, &target_dir, section.title, &id);
+        write_atomic(&vault.root.join(&target_rel), &contents)?;
+        vault.record_step(Step::CreateFile {
+            path: target_rel.clone(),
+            contents: contents.clone(),
+        });
+        vault.index.refresh_note(&vault.root, &target_rel);
+        links.push(format!("- [[{}|{}]]", id, section.title));
+        parts.push(CreateNoteResult {
+            id,
+            relative_path: target_rel,
+            contents,
+        });
+    }
+
+    let preamble = preamble.trim_end();
+    let mut body = String::new();
+    if !preamble.is_empty() {
+        body.push_str(preamble);
+        body.push_str("\n\n");
+    }
+    body.push_str(&links.join("\n"));
+    body.push('\n');
+    note.body = body;
+    note.meta.modified = Some(now_iso);
+    let contents = note.render().map_err(VaultError::internal)?;
+
+    // Undo puts the original back; the forced snapshot also keeps it in the
+    // note's history once the journal has moved on (for ids history can
+    // store).
+    let full = vault.root.join(rel);
+    let before = fs::read_to_string(&full).map_err(|e| VaultError::io(rel, e))?;
+    history::record(&vault.root, &full, &contents, true)?;
+    write_atomic(&full, &contents)?;
+    vault.record_step(Step::Write {
+        path: rel.to_string(),
+        before,
+        after: contents.clone(),
+    });
+    vault.index.refresh_note(&vault.root, rel);
+    Ok((contents, parts))
+}
+
+/// Splits a note into one note per heading of `level` (1-6). The parts land in
+/// the note's folder and inherit its projects and topics; the original keeps
+/// any text before the first split heading, followed by links to the parts.
+#[tauri::command]
+pub fn vault_split_note(
+    state: State<'_, VaultState>,
+    relative_path: String,
+    level: usize,
+) -> VaultResult<SplitNoteResult> {
+    state.with_writable_vault(|vault| {
+        if !(1..=6).contains(&level) {
+            return Err(VaultError::invalid_input(
+                "level",
+                "must be between 1 and 6",
+            ));
+        }
+        let (rel, note) = read_parsed_note(vault, &relative_path)?;
+        if rel.starts_with("notes/archive/") {
+            return Err(VaultError::invalid_path(
+                &rel,
+                "cannot split an archived note",
+            ));
+        }
+        let (contents, parts) = match apply_split(vault, &rel, note, level) {
+            Ok(done) => done,
+            Err(e) => {
+                // Never leave some parts written and the original untouched.
+                journal::roll_back(vault);
+                return Err(e);
+            }
+        };
+        journal::commit(vault, "Split note");
+        Ok(SplitNoteResult {
+            relative_path: rel,
+            contents,
+            parts,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles<'a>(parts: &[Part<'a>]) -> Vec<&'a str> {
+        parts.iter().map(|p| p.title).collect()
+    }
+
+    #[test]
+    fn cuts_at_each_heading_of_the_level() {
+        let body = "Intro\n\n## One\nfirst\n## Two\nsecond\n";
+        let (preamble, parts) = split_body(body, 2);
+        assert_eq!(preamble, "Intro\n\n");
+        assert_eq!(titles(&parts), ["One", "Two"]);
+        assert_eq!(parts[0].body, "## One\nfirst\n");
+        assert_eq!(parts[1].body, "## Two\nsecond\n");
+    }
+
+    #[test]
+    fn deeper_headings_stay_inside_their_part() {
+        let body = "## One\n### Detail\ntext\n## Two\n";
+        let (_, parts) = split_body(body, 2);
+        assert_eq!(titles(&parts), ["One", "Two"]);
+        assert_eq!(parts[0].body, "## One\n### Detail\ntext\n");
+    }
+
+    #[test]
+    fn shallower_headings_after_the_first_cut_also_cut() {
+        let body = "# Doc\n## One\na\n# Appendix\nb\n";
+        let (preamble, parts) = split_body(body, 2);
+        assert_eq!(preamble, "# Doc\n");
+        assert_eq!(titles(&parts), ["One", "Appendix"]);
+    }
+
+    #[test]
+    fn headings_in_code_fences_are_ignored() {
+        let body = "## One\n```\n## not a heading\n```\n";
+        let (_, parts) = split_body(body, 2);
+        assert_eq!(titles(&parts), ["One"]);
+        assert_eq!(parts[0].body, body);
+    }
+
+    #[test]
+    fn no_heading_at_the_level_means_no_parts() {
+        let (preamble, parts) = split_body("# Only\ntext\n", 2);
+        assert_eq!(preamble, "# Only\ntext\n");
+        assert!(parts.is_empty());
+    }
+}
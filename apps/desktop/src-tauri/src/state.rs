@@ -0,0 +1,230 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::{VaultError, VaultResult},
+    index::{self, VaultIndex},
+    journal::Step,
+    lock::{self, LockStatus, VaultLock},
+    migrations, registry, thumbs, trash,
+    vault::{ensure_vault_structure, path_to_forward_slashes, validate_relative_path, VaultInfo},
+};
+
+/// How long an id that could not be resolved is answered as missing without
+/// looking at the disk again, so a dangling link rendered many times does not
+/// rescan the vault each time.
+const MISSING_ID_TTL: Duration = Duration::from_secs(5);
+
+/// The vault this process currently works against. Opened once (on `vault_init`
+/// or the first command that needs it) and replaced when the user switches vaults.
+pub(crate) struct OpenVault {
+    pub root: PathBuf,
+    pub version: u32,
+    pub index: VaultIndex,
+    lock: VaultLock,
+    /// Structural changes made by the current command, for `journal::commit`.
+    steps: Vec<Step>,
+    /// Ids recently found in no note, and when.
+    missing_ids: HashMap<String, Instant>,
+}
+
+impl OpenVault {
+    fn open(root: PathBuf) -> VaultResult<Self> {
+        let lock = lock::acquire(&root)?;
+        let version = match lock.status() {
+            LockStatus::Held => {
+                ensure_vault_structure(&root)?;
+                let version = migrations::migrate_vault(&root)?;
+                // Best effort: an unreadable trash entry must not keep the vault
+                // from opening.
+                let _ = trash::purge_expired(&root);
+                thumbs::prune_thumbnails(&root);
+                version
+            }
+            // The lock holder owns structure and migrations; just make sure we
+            // can read what it wrote.
+            LockStatus::ReadOnly { .. } => migrations::check_vault_version(&root)?,
+        };
+        let index = VaultIndex::build(&root, matches!(lock.status(), LockStatus::Held))?;
+        Ok(OpenVault {
+            root,
+            version,
+            index,
+            lock,
+            steps: Vec::new(),
+            missing_ids: HashMap::new(),
+        })
+    }
+
+    pub(crate) fn info(&self) -> VaultInfo {
+        let lock_holder = match self.lock.status() {
+            LockStatus::Held => None,
+            LockStatus::ReadOnly { holder } => Some(holder),
+        };
+        VaultInfo {
+            vault_path: self.root.to_string_lossy().to_string(),
+            version: self.version,
+            read_only: lock_holder.is_some(),
+            lock_holder,
+        }
+    }
+
+    pub(crate) fn is_read_only(&self) -> bool {
+        matches!(self.lock.status(), LockStatus::ReadOnly { .. })
+    }
+
+    pub(crate) fn ensure_writable(&self) -> VaultResult<()> {
+        self.lock.ensure_writable()
+    }
+
+    pub(crate) fn resolve(&self, relative_path: &str) -> VaultResult<PathBuf> {
+        Ok(self.root.join(validate_relative_path(relative_path)?))
+    }
+
+    pub(crate) fn relative(&self, full_path: &Path) -> VaultResult<String> {
+        let rel = full_path
+            .strip_prefix(&self.root)
+            .map_err(|_| VaultError::OutsideVault {
+                path: path_to_forward_slashes(full_path),
+            })?;
+        Ok(path_to_forward_slashes(rel))
+    }
+
+    /// Looks up the note carrying `id`. A stale or missing entry means the note
+    /// was renamed or moved outside the app, so the index is caught up with the
+    /// disk once before giving up.
+    pub(crate) fn resolve_note_id(&mut self, id: &str) -> VaultResult<String> {
+        if let Some(rel) = self.index.note_path(id) {
+            if index::read_note_id(&self.root.join(rel)).as_deref() == Some(id) {
+                return Ok(rel.to_string());
+            }
+        }
+        let not_found = || VaultError::IdNotFound {
+            kind: "note",
+            id: id.to_string(),
+        };
+        self.missing_ids
+            .retain(|_, missed_at| missed_at.elapsed() < MISSING_ID_TTL);
+        if self.missing_ids.contains_key(id) {
+            return Err(not_found());
+        }
+        self.sync_index()?;
+        match self.index.note_path(id) {
+            Some(rel) => Ok(rel.to_string()),
+            None => {
+                self.missing_ids.insert(id.to_string(), Instant::now());
+                Err(not_found())
+            }
+        }
+    }
+
+    pub(crate) fn record_step(&mut self, step: Step) {
+        self.steps.push(step);
+    }
+
+    pub(crate) fn take_steps(&mut self) -> Vec<Step> {
+        std::mem::take(&mut self.steps)
+    }
+
+    /// Catches the index up with changes made outside the app. Listing answers
+    /// from memory; this runs on an explicit refresh, on an unknown note id,
+    /// and before commands that must not miss a note (asset clean-up,
+    /// relinking).
+    pub(crate) fn sync_index(&mut self) -> VaultResult<()> {
+        self.index.reconcile(&self.root)
+    }
+
+    /// Re-walks the disk, for callers that changed the vault behind the index's
+    /// back (repairs, external edits).
+    pub(crate) fn reindex(&mut self) -> VaultResult<()> {
+        self.index = VaultIndex::build(&self.root, !self.is_read_only())?;
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct VaultState {
+    inner: Mutex<Option<OpenVault>>,
+}
+
+impl VaultState {
+    /// Opens the vault at `root` and only then replaces (and unlocks) the
+    /// current one, so a vault that fails to open leaves the old one in place.
+    pub(crate) fn open(&self, root: PathBuf) -> VaultResult<VaultInfo> {
+        let mut inner = self
+            .inner
+            .lock()
+            .map_err(|_| VaultError::internal("Vault state poisoned"))?;
+        if let Some(current) = inner.as_ref() {
+            // Already holding this vault's lock: opening it again would find
+            // our own lock file held. A read-only handle holds no lock, so it
+            // is retried below like any other vault.
+            if current.root == root && !current.is_read_only() {
+                return Ok(current.info());
+            }
+        }
+        let vault = OpenVault::open(root)?;
+        let info = vault.info();
+        *inner = Some(vault);
+        Ok(info)
+    }
+
+    /// Opens the registry's active vault unless it is already open. A vault that
+    /// was opened read-only is retried, so the UI can re-run `vault_init` once
+    /// the other process has gone away.
+    pub(crate) fn open_active(&self) -> VaultResult<VaultInfo> {
+        let root = registry::active_vault_root()?;
+        {
+            let inner = self
+                .inner
+                .lock()
+                .map_err(|_| VaultError::internal("Vault state poisoned"))?;
+            if let Some(vault) = inner.as_ref() {
+                if vault.root == root && !vault.is_read_only() {
+                    return Ok(vault.info());
+                }
+            }
+        }
+        self.open(root)
+    }
+
+    pub(crate) fn with_vault<T>(
+        &self,
+        f: impl FnOnce(&mut OpenVault) -> VaultResult<T>,
+    ) -> VaultResult<T> {
+        let mut inner = self
+            .inner
+            .lock()
+            .map_err(|_| VaultError::internal("Vault state poisoned"))?;
+        if inner.is_none() {
+            *inner = Some(OpenVault::open(registry::active_vault_root()?)?);
+        }
+        match inner.as_mut() {
+            Some(vault) => f(vault),
+            None => Err(VaultError::internal("No vault is open")),
+        }
+    }
+
+    /// Like `with_vault`, but refuses to run `f` on a read-only vault.
+    pub(crate) fn with_writable_vault<T>(
+        &self,
+        f: impl FnOnce(&mut OpenVault) -> VaultResult<T>,
+    ) -> VaultResult<T> {
+        self.with_vault(|vault| {
+            vault.ensure_writable()?;
+            // Steps left over from a command that failed are not undoable.
+            vault.steps.clear();
+            f(vault)
+        })
+    }
+
+    pub(crate) fn close(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            *inner = None;
+        }
+    }
+}
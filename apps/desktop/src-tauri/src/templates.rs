@@ -0,0 +1,380 @@
+use chrono::{Duration, Local, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    error::{VaultError, VaultResult},
+    frontmatter::{self, Note, NoteMeta},
+    naming,
+    state::VaultState,
+    vault::{validate_note_target_dir, write_atomic, CreateNoteResult},
+};
+
+pub(crate) const TEMPLATES_DIR: &str = "config/templates";
+/// Stands in for `{{cursor}}` until the note is fully rendered; a private-use
+/// character, so it cannot clash with anything a user types.
+const CURSOR_MARK: char = '\u{E000}';
+
+/// Values available to `{{...}}` placeholders.
+pub(crate) struct TemplateContext<'a> {
+    /// `{{date}}`, and the base for `{{date+1d}}` and friends.
+    pub date: NaiveDate,
+    pub title: &'a str,
+    pub project: &'a str,
+}
+
+/// Template names are file stems in `config/templates`, so they must stay a
+/// single path component.
+fn template_path(vault_root: &Path, name: &str) -> VaultResult<PathBuf> {
+    let name = name.trim();
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) || name.contains("..")
+    {
+        return Err(VaultError::invalid_input(
+            "template",
+            "is not a valid template name",
+        ));
+    }
+    Ok(vault_root.join(TEMPLATES_DIR).join(format!("{}.md", name)))
+}
+
+/// `date+1d`, `date-2w`, `date+3m`, `date+1y`; `None` for anything else.
+fn shifted_date(expr: &str, base: NaiveDate) -> Option<NaiveDate> {
+    let offset = expr.strip_prefix("date")?.trim();
+    if offset.is_empty() {
+        return Some(base);
+    }
+    let (forward, rest) = match offset.strip_prefix('+') {
+        Some(rest) => (true, rest),
+        None => (false, offset.strip_prefix('-')?),
+    };
+    let unit = rest.chars().last()?;
+    let amount: u32 = rest[..rest.len() - unit.len_utf8()].trim().parse().ok()?;
+    let shift_months = |months: u32| {
+        if forward {
+            base.checked_add_months(Months::new(months))
+        } else {
+            base.checked_sub_months(Months::new(months))
+        }
+    };
+    match unit {
+        'd' | 'w' => {
+            let days = i64::from(amount) * if unit == 'w' { 7 } else { 1 };
+            base.checked_add_signed(Duration::days(if forward { days } else { -days }))
+        }
+        'm' => shift_months(amount),
+        'y' => shift_months(amount.checked_mul(12)?),
+        _ => None,
+    }
+}
+
+/// Replaces known placeholders; unknown ones are left as written.
+fn substitute(template: &str, ctx: &TemplateContext) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 2..];
+        let Some(close) = after.find("}}") else {
+            rest = &rest[open..];
+            break;
+        };
+        let expr = after[..close].trim();
+        match expr {
+            "title" => out.push_str(ctx.title),
+            "project" => out.push_str(ctx.project),
+            "time" => out.push_str(&Local::now().format("%H:%M").to_string()),
+            "cursor" => out.push(CURSOR_MARK),
+            _ => match shifted_date(expr, ctx.date) {
+                Some(date) => out.push_str(&date.format("%Y-%m-%d").to_string()),
+                None => out.push_str(&rest[open..open + 2 + close + 2]),
+            },
+        }
+        rest = &after[close + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `{{expr}}` written as a bare YAML value parses as a flow mapping holding a
+/// flow mapping; gives back the placeholder when `value` is one.
+fn bare_placeholder(value: &Value) -> Option<String> {
+    let Value::Mapping(outer) = value else {
+        return None;
+    };
+    let (Value::Mapping(inner), Value::Null) = outer.iter().next()? else {
+        return None;
+    };
+    let (Value::String(expr), Value::Null) = inner.iter().next()? else {
+        return None;
+    };
+    (outer.len() == 1 && inner.len() == 1).then(|| format!("{{{{{}}}}}", expr))
+}
+
+/// Substitutes into every string in `value`, leaving keys and structure alone.
+fn substitute_value(value: &mut Value, ctx: &TemplateContext) {
+    if let Some(placeholder) = bare_placeholder(value) {
+        *value = Value::String(substitute(&placeholder, ctx));
+        return;
+    }
+    match value {
+        Value::String(s) => *s = substitute(s, ctx),
+        Value::Sequence(items) => items.iter_mut().for_each(|v| substitute_value(v, ctx)),
+        Value::Mapping(map) => map.values_mut().for_each(|v| substitute_value(v, ctx)),
+        Value::Tagged(tagged) => substitute_value(&mut tagged.value, ctx),
+        _ => {}
+    }
+}
+
+/// Parses a template, then fills in placeholders in its body and in the
+/// string values of its frontmatter. Substituting first would let a title
+/// like `Re: plan` change the YAML's meaning or break it.
+fn parse_template(raw: &str, ctx: &TemplateContext) -> Result<Note, serde_yaml::Error> {
+    let Some((yaml, rest)) = frontmatter::split(raw) else {
+        return Ok(Note::new(NoteMeta::default(), substitute(raw, ctx)));
+    };
+    let meta = if yaml.trim().is_empty() {
+        NoteMeta::default()
+    } else {
+        let mut value: Value = serde_yaml::from_str(yaml)?;
+        substitute_value(&mut value, ctx);
+        serde_yaml::from_value(value)?
+    };
+    // As in `Note::parse`: the blank line after the fence is layout.
+    let body = rest
+        .strip_prefix("\r\n")
+        .or_else(|| rest.strip_prefix('\n'))
+        .unwrap_or(rest);
+    Ok(Note::new(meta, substitute(body, ctx)))
+}
+
+/// Renders template `name` into a full note. `meta` supplies the fields
+/// Homebase owns (id, dates, placement); the template's own frontmatter adds
+/// projects, topics and any other keys. Also returns where `{{cursor}}` was,
+/// as an offset in UTF-16 code units (the way JavaScript indexes strings).
+pub(crate) fn render_template(
+    vault_root: &Path,
+    name: &str,
+    ctx: &TemplateContext,
+    meta: NoteMeta,
+) -> VaultResult<(String, Option<usize>)> {
+    let path = template_path(vault_root, name)?;
+    let raw = match fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(VaultError::IdNotFound {
+                kind: "template",
+                id: name.to_string(),
+            })
+        }
+        Err(e) => return Err(VaultError::io(&path, e)),
+    };
+    let template = parse_template(&raw, ctx).map_err(|e| VaultError::invalid_data(&path, e))?;
+
+    let mut merged = NoteMeta {
+        id: meta.id,
+        created: meta.created,
+        modified: meta.modified,
+        user_placed: meta.user_placed,
+        daily_date: meta.daily_date,
+        ..template.meta
+    };
+    for project in meta.projects {
+        if !merged.projects.contains(&project) {
+            merged.projects.push(project);
+        }
+    }
+    let rendered = Note::new(merged, template.body)
+        .render()
+        .map_err(VaultError::internal)?;
+
+    let cursor = rendered
+        .find(CURSOR_MARK)
+        .map(|at| rendered[..at].encode_utf16().count());
+    Ok((rendered.replace(CURSOR_MARK, ""), cursor))
+}
+
+/// Whether `name` exists, for callers that fall back to a blank note.
+pub(crate) fn template_exists(vault_root: &Path, name: &str) -> bool {
+    template_path(vault_root, name).is_ok_and(|path| path.is_file())
+}
+
+/// Names of the templates in `config/templates`, sorted.
+#[tauri::command]
+pub fn vault_list_templates(state: State<'_, VaultState>) -> VaultResult<Vec<String>> {
+    state.with_vault(|vault| {
+        let mut names: Vec<String> = fs::read_dir(vault.root.join(TEMPLATES_DIR))
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let name = e.file_name().to_str()?.strip_suffix(".md")?.to_string();
+                (!name.starts_with('.')).then_some(name)
+            })
+            .collect();
+        names.sort();
+        Ok(names)
+    })
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateNoteFromTemplateArgs {
+    pub template: String,
+    pub title: Option<String>,
+    /// Fills `{{project}}`, tags the note with the project and, unless
+    /// `target_dir` says otherwise, files it in the project's folder.
+    pub project_id: Option<String>,
+    pub target_dir: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateNoteFromTemplateResult {
+    #[serde(flatten)]
+    pub note: CreateNoteResult,
+    pub cursor: Option<usize>,
+}
+
+#[tauri::command]
+pub fn vault_create_note_from_template(
+    state: State<'_, VaultState>,
+    args: CreateNoteFromTemplateArgs,
+) -> VaultResult<CreateNoteFromTemplateResult> {
+    state.with_writable_vault(|vault| {
+        let project =
+            match &args.project_id {
+                Some(id) => Some(vault.index.project(id).cloned().ok_or_else(|| {
+                    VaultError::IdNotFound {
+                        kind: "project",
+                        id: id.clone(),
+                    }
+                })?),
+                None => None,
+            };
+        let target_dir = match (&args.target_dir, &project) {
+            (Some(dir), _) => dir.clone(),
+            (None, Some(project)) => project.folder_relative_path.clone(),
+            (None, None) => "notes/inbox".to_string(),
+        };
+        let target_dir = validate_note_target_dir(&target_dir)?;
+        let target_dir = target_dir.trim_end_matches('/');
+
+        let id = Uuid::new_v4().to_string();
+        let now_iso = Utc::now().to_rfc3339();
+        let mut meta = NoteMeta::new(id.clone(), &now_iso);
+        meta.user_placed = Some(target_dir != "notes/inbox");
+        if let Some(project) = &project {
+            meta.projects.push(project.meta.id.clone());
+        }
+
+        let title = args.title.as_deref().map(str::trim).unwrap_or_default();
+        let ctx = TemplateContext {
+            date: Local::now().date_naive(),
+            title: if title.is_empty() { "Untitled" } else { title },
+            project: project.as_ref().map_or("", |p| p.meta.name.as_str()),
+        };
+        let (contents, cursor) = render_template(&vault.root, &args.template, &ctx, meta)?;
+
+        let file_title = if title.is_empty() {
+            Note::parse(&contents)
+                .ok()
+                .and_then(|note| naming::first_heading(&note.body).map(str::to_string))
+                .unwrap_or_else(|| "untitled".to_string())
+        } else {
+            title.to_string()
+        };
+        let rel = naming::new_note_path(&vault.root, target_dir, &file_title, &id);
+        write_atomic(&vault.root.join(&rel), &contents)?;
+        vault.index.refresh_note(&vault.root, &rel);
+        Ok(CreateNoteFromTemplateResult {
+            note: CreateNoteResult {
+                id,
+                relative_path: rel,
+                contents,
+            },
+            cursor,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn shifts_by_each_unit() {
+        let base = date("2024-01-31");
+        assert_eq!(shifted_date("date", base), Some(base));
+        assert_eq!(shifted_date("date+1d", base), Some(date("2024-02-01")));
+        assert_eq!(shifted_date("date-2w", base), Some(date("2024-01-17")));
+        // Month ends clamp rather than overflow.
+        assert_eq!(shifted_date("date+1m", base), Some(date("2024-02-29")));
+        assert_eq!(shifted_date("date-1y", base), Some(date("2023-01-31")));
+        assert_eq!(shifted_date("date + 3d", base), Some(date("2024-02-03")));
+    }
+
+    #[test]
+    fn rejects_other_expressions() {
+        let base = date("2024-01-31");
+        for expr in [
+            "dates", "date+", "date+1", "date+1h", "date*2d", "date+-1d", "title",
+        ] {
+            assert_eq!(shifted_date(expr, base), None, "{}", expr);
+        }
+    }
+
+    #[test]
+    fn substitutes_known_placeholders_only() {
+        let ctx = TemplateContext {
+            date: date("2024-03-10"),
+            title: "Plan",
+            project: "Home",
+        };
+        assert_eq!(
+            substitute(
+                "# {{title}} ({{ project }})\n{{date-1d}} {{unknown}} {{open",
+                &ctx
+            ),
+            "# Plan (Home)\n2024-03-09 {{unknown}} {{open"
+        );
+        assert_eq!(
+            substitute("a{{cursor}}b", &ctx),
+            format!("a{}b", CURSOR_MARK)
+        );
+    }
+
+    #[test]
+    fn placeholders_in_frontmatter_stay_strings() {
+        let ctx = TemplateContext {
+            date: date("2024-03-10"),
+            title: "Re: plan #2",
+            project: "- Home",
+        };
+        let raw = "---\ntopics: [{{title}}]\nsummary: {{title}}\nsource: \"From {{project}}\"\nday: {{date}}\n---\n\n# {{title}}\n";
+        let note = parse_template(raw, &ctx).unwrap();
+        assert_eq!(note.meta.topics, ["Re: plan #2"]);
+        assert_eq!(
+            note.meta.extras.get("summary"),
+            Some(&Value::from("Re: plan #2"))
+        );
+        assert_eq!(
+            note.meta.extras.get("source"),
+            Some(&Value::from("From - Home"))
+        );
+        assert_eq!(
+            note.meta.extras.get("day"),
+            Some(&Value::from("2024-03-10"))
+        );
+        assert_eq!(note.body, "# Re: plan #2\n");
+    }
+}
@@ -0,0 +1,122 @@
+use image::{ImageFormat, ImageReader};
+use std::{collections::HashSet, fs, io::Cursor, path::Path, time::UNIX_EPOCH};
+use tauri::http::{header, Response, StatusCode};
+use walkdir::WalkDir;
+
+use crate::{
+    assets::{percent_decode, ASSETS_DIR},
+    error::{VaultError, VaultResult},
+    state::VaultState,
+    vault::{content_hash, path_to_forward_slashes, validate_relative_path, write_atomic},
+};
+
+/// URI scheme the webview loads thumbnails from, e.g.
+/// `thumb://localhost/assets%2Fab12cd34.png`.
+pub const THUMB_SCHEME: &str = "thumb";
+const THUMBS_DIR: &str = ".homebase/cache/thumbs";
+/// Longest edge of a thumbnail, in pixels.
+const THUMB_SIZE: u32 = 256;
+
+/// Name of the cached thumbnail for the asset at `rel` as it is now. Keyed on
+/// the path, mtime and size, so a cache hit never reads the asset itself and
+/// an edited asset gets a fresh thumbnail.
+fn cache_name(rel: &str, meta: &fs::Metadata) -> String {
+    let mtime_ns = meta
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_nanos());
+    let key = content_hash(format!("{}\n{}\n{}", rel, mtime_ns, meta.len()).as_bytes());
+    format!("{}-{}.png", key, THUMB_SIZE)
+}
+
+/// The thumbnail of image asset `rel` as PNG bytes. A read-only vault belongs
+/// to another process, so its cache is used but never written.
+fn thumbnail(vault_root: &Path, read_only: bool, rel: &str) -> VaultResult<Vec<u8>> {
+    let rel = path_to_forward_slashes(&validate_relative_path(rel)?);
+    if !rel.starts_with(&format!("{}/", ASSETS_DIR)) {
+        return Err(VaultError::invalid_path(&rel, "must be under assets/"));
+    }
+    let source = vault_root.join(&rel);
+    let meta = fs::metadata(&source).map_err(|e| VaultError::io(&rel, e))?;
+    let cached = vault_root.join(THUMBS_DIR).join(cache_name(&rel, &meta));
+    if let Ok(thumb) = fs::read(&cached) {
+        return Ok(thumb);
+    }
+
+    let bytes = fs::read(&source).map_err(|e| VaultError::io(&rel, e))?;
+    let image = ImageReader::new(Cursor::new(&bytes))
+        .with_guessed_format()
+        .map_err(|e| VaultError::io(&rel, e))?
+        .decode()
+        .map_err(|e| VaultError::invalid_data(&rel, e))?;
+    let mut thumb = Vec::new();
+    image
+        .thumbnail(THUMB_SIZE, THUMB_SIZE)
+        .write_to(&mut Cursor::new(&mut thumb), ImageFormat::Png)
+        .map_err(VaultError::internal)?;
+    if !read_only {
+        write_atomic(&cached, &thumb)?;
+    }
+    Ok(thumb)
+}
+
+/// Deletes cached thumbnails that match no asset as it is now: assets that
+/// were edited, renamed or removed since. Best effort.
+pub(crate) fn prune_thumbnails(vault_root: &Path) {
+    let live: HashSet<String> = WalkDir::new(vault_root.join(ASSETS_DIR))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let rel = path_to_forward_slashes(e.path().strip_prefix(vault_root).ok()?);
+            Some(cache_name(&rel, &e.metadata().ok()?))
+        })
+        .collect();
+    for entry in fs::read_dir(vault_root.join(THUMBS_DIR))
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+    {
+        // Dot files are thumbnails still being written.
+        let stale = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| !name.starts_with('.') && !live.contains(name));
+        if stale {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+fn status_for(err: &VaultError) -> StatusCode {
+    match err {
+        VaultError::NotFound { .. } => StatusCode::NOT_FOUND,
+        VaultError::InvalidPath { .. }
+        | VaultError::InvalidInput { .. }
+        | VaultError::InvalidData { .. } => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Answers a `thumb://` request. The path is the percent-encoded,
+/// vault-relative path of the asset.
+pub fn respond(state: &VaultState, uri_path: &str) -> Response<Vec<u8>> {
+    let rel = percent_decode(uri_path.trim_start_matches('/'));
+    // Decoding runs outside the vault lock so commands are not held up.
+    let result = state
+        .with_vault(|vault| Ok((vault.root.clone(), vault.is_read_only())))
+        .and_then(|(root, read_only)| thumbnail(&root, read_only, &rel));
+    let response = match result {
+        Ok(thumb) => Response::builder()
+            .header(header::CONTENT_TYPE, "image/png")
+            // Same URL, new contents after an edit: always revalidate.
+            .header(header::CACHE_CONTROL, "no-cache")
+            .body(thumb),
+        Err(e) => Response::builder()
+            .status(status_for(&e))
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(e.to_string().into_bytes()),
+    };
+    response.unwrap_or_else(|_| Response::new(Vec::new()))
+}
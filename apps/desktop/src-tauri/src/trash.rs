@@ -0,0 +1,275 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    error::{VaultError, VaultResult},
+    journal::{self, Step},
+    moves::free_variant,
+    settings,
+    state::{OpenVault, VaultState},
+    vault::{path_to_forward_slashes, validate_relative_path, write_atomic},
+};
+
+const TRASH_DIR: &str = ".homebase/trash";
+const ENTRY_FILE: &str = "entry.json";
+/// The trashed file or folder itself, renamed so its original name can never
+/// collide with `entry.json`.
+const PAYLOAD: &str = "payload";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TrashKind {
+    Note,
+    Folder,
+    Asset,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashEntry {
+    pub id: String,
+    pub kind: TrashKind,
+    pub original_path: String,
+    pub deleted_at: String,
+}
+
+fn entry_dir(vault_root: &Path, id: &str) -> PathBuf {
+    vault_root.join(TRASH_DIR).join(id)
+}
+
+fn read_entries(vault_root: &Path) -> Vec<TrashEntry> {
+    let mut entries: Vec<TrashEntry> = fs::read_dir(vault_root.join(TRASH_DIR))
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let raw = fs::read_to_string(e.path().join(ENTRY_FILE)).ok()?;
+            let entry: TrashEntry = serde_json::from_str(&raw).ok()?;
+            // The id doubles as the directory name, so never trust one that
+            // points anywhere else.
+            (e.file_name().to_str() == Some(entry.id.as_str())).then_some(entry)
+        })
+        .collect();
+    entries.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    entries
+}
+
+fn remove_entry(vault_root: &Path, id: &str) -> VaultResult<()> {
+    let dir = entry_dir(vault_root, id);
+    fs::remove_dir_all(&dir).map_err(|e| VaultError::io(&dir, e))
+}
+
+/// Moves `rel` into a new trash entry; the caller updates the index.
+pub(crate) fn move_to_trash(
+    vault: &OpenVault,
+    rel: &str,
+    kind: TrashKind,
+) -> VaultResult<TrashEntry> {
+    let entry = TrashEntry {
+        id: Uuid::new_v4().to_string(),
+        kind,
+        original_path: rel.to_string(),
+        deleted_at: Utc::now().to_rfc3339(),
+    };
+    let dir = entry_dir(&vault.root, &entry.id);
+    fs::create_dir_all(&dir).map_err(|e| VaultError::io(&dir, e))?;
+    let raw = serde_json::to_string_pretty(&entry).map_err(VaultError::internal)?;
+    write_atomic(&dir.join(ENTRY_FILE), &raw)?;
+
+    let source = vault.root.join(rel);
+    if let Err(e) = fs::rename(&source, dir.join(PAYLOAD)) {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(VaultError::io(rel, e));
+    }
+    Ok(entry)
+}
+
+/// `rel` itself if free, else `name-restored.md`, `name-restored-2.md`, ...
+fn free_restore_path(vault_root: &Path, rel: &str) -> String {
+    if !vault_root.join(rel).exists() {
+        return rel.to_string();
+    }
+    free_variant(vault_root, rel, |n| match n {
+        1 => "-restored".to_string(),
+        n => format!("-restored-{}", n),
+    })
+}
+
+/// Deletes entries older than the vault's `trashRetentionDays` setting.
+pub(crate) fn purge_expired(vault_root: &Path) -> VaultResult<usize> {
+    let days = settings::load(vault_root)?.trash_retention_days;
+    if days == 0 {
+        return Ok(0);
+    }
+    let cutoff = Utc::now() - Duration::days(i64::from(days));
+    let expired = read_entries(vault_root).into_iter().filter(|entry| {
+        DateTime::parse_from_rfc3339(&entry.deleted_at).is_ok_and(|deleted_at| deleted_at < cutoff)
+    });
+    purge(vault_root, expired)
+}
+
+/// Deletes trash entries for good, and the journal entries that would need
+/// them to replay.
+fn purge(vault_root: &Path, entries: impl IntoIterator<Item = TrashEntry>) -> VaultResult<usize> {
+    let mut purged = HashSet::new();
+    let mut result = Ok(());
+    for entry in entries {
+        result = remove_entry(vault_root, &entry.id);
+        if result.is_err() {
+            break;
+        }
+        purged.insert(entry.id);
+    }
+    // Whatever was removed before a failure is gone all the same.
+    journal::forget_trash_entries(vault_root, &purged)?;
+    result.map(|_| purged.len())
+}
+
+/// Validates that `relative_path` is a note that can be trashed.
+pub(crate) fn trash_note_source(vault: &OpenVault, relative_path: &str) -> VaultResult<String> {
+    let rel = path_to_forward_slashes(&validate_relative_path(relative_path)?);
+    if !rel.starts_with("notes/") || !rel.ends_with(".md") {
+        return Err(VaultError::invalid_path(
+            &rel,
+            "must be a note under notes/",
+        ));
+    }
+    if !vault.root.join(&rel).is_file() {
+        return Err(VaultError::not_found(&rel));
+    }
+    Ok(rel)
+}
+
+pub(crate) fn trash_note(vault: &mut OpenVault, relative_path: &str) -> VaultResult<TrashEntry> {
+    let rel = trash_note_source(vault, relative_path)?;
+    let entry = move_to_trash(vault, &rel, TrashKind::Note)?;
+    vault.record_step(Step::Trash {
+        path: rel.clone(),
+        entry_id: entry.id.clone(),
+    });
+    vault.index.refresh_note(&vault.root, &rel);
+    Ok(entry)
+}
+
+#[tauri::command]
+pub fn vault_trash_note(
+    state: State<'_, VaultState>,
+    relative_path: String,
+) -> VaultResult<TrashEntry> {
+    state.with_writable_vault(|vault| {
+        let entry = trash_note(vault, &relative_path)?;
+        journal::commit(vault, "Trash note");
+        Ok(entry)
+    })
+}
+
+/// Trashes a folder together with everything inside it.
+#[tauri::command]
+pub fn vault_trash_folder(
+    state: State<'_, VaultState>,
+    relative_path: String,
+) -> VaultResult<TrashEntry> {
+    state.with_writable_vault(|vault| {
+        let rel = path_to_forward_slashes(&validate_relative_path(&relative_path)?);
+        let rel = rel.trim_end_matches('/').to_string();
+        if !rel.starts_with("notes/folders/") {
+            return Err(VaultError::invalid_path(
+                &rel,
+                "must start with notes/folders/",
+            ));
+        }
+        if !vault.root.join(&rel).is_dir() {
+            return Err(VaultError::not_found(&rel));
+        }
+        let entry = move_to_trash(vault, &rel, TrashKind::Folder)?;
+        vault.reindex()?;
+        vault.record_step(Step::Trash {
+            path: rel,
+            entry_id: entry.id.clone(),
+        });
+        journal::commit(vault, "Trash folder");
+        Ok(entry)
+    })
+}
+
+#[tauri::command]
+pub fn vault_list_trash(state: State<'_, VaultState>) -> VaultResult<Vec<TrashEntry>> {
+    state.with_vault(|vault| Ok(read_entries(&vault.root)))
+}
+
+/// Holds an entry's `original_path` to where its kind can be trashed from, so
+/// an edited `entry.json` cannot restore into `.homebase/` or `config/`.
+fn restore_source(entry: &TrashEntry) -> VaultResult<String> {
+    let rel = path_to_forward_slashes(&validate_relative_path(&entry.original_path)?);
+    let (allowed, expected) = match entry.kind {
+        TrashKind::Note => (
+            rel.starts_with("notes/") && rel.ends_with(".md"),
+            "must be a note under notes/",
+        ),
+        TrashKind::Folder => (
+            rel.starts_with("notes/folders/"),
+            "must start with notes/folders/",
+        ),
+        TrashKind::Asset => (rel.starts_with("assets/"), "must start with assets/"),
+    };
+    if !allowed {
+        return Err(VaultError::invalid_path(&rel, expected));
+    }
+    Ok(rel)
+}
+
+/// Puts an entry back at its original path, or next to it if that path has
+/// been taken since, and returns where it ended up.
+pub(crate) fn restore_entry(vault: &mut OpenVault, id: &str) -> VaultResult<String> {
+    let entry = read_entries(&vault.root)
+        .into_iter()
+        .find(|e| e.id == id)
+        .ok_or_else(|| VaultError::IdNotFound {
+            kind: "trash entry",
+            id: id.to_string(),
+        })?;
+    let original_rel = restore_source(&entry)?;
+
+    let target_rel = free_restore_path(&vault.root, &original_rel);
+    let target = vault.root.join(&target_rel);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| VaultError::io(parent, e))?;
+    }
+    let payload = entry_dir(&vault.root, &entry.id).join(PAYLOAD);
+    fs::rename(&payload, &target).map_err(|e| VaultError::io(&payload, e))?;
+    remove_entry(&vault.root, &entry.id)?;
+
+    match entry.kind {
+        TrashKind::Note => vault.index.refresh_note(&vault.root, &target_rel),
+        TrashKind::Folder => vault.reindex()?,
+        TrashKind::Asset => {}
+    }
+    Ok(target_rel)
+}
+
+#[tauri::command]
+pub fn vault_restore_from_trash(state: State<'_, VaultState>, id: String) -> VaultResult<String> {
+    state.with_writable_vault(|vault| {
+        let path = restore_entry(vault, &id)?;
+        vault.record_step(Step::Untrash {
+            entry_id: id,
+            path: path.clone(),
+        });
+        journal::commit(vault, "Restore from trash");
+        Ok(path)
+    })
+}
+
+/// Permanently deletes everything in the trash and returns how many entries
+/// were removed.
+#[tauri::command]
+pub fn vault_empty_trash(state: State<'_, VaultState>) -> VaultResult<usize> {
+    state.with_writable_vault(|vault| purge(&vault.root, read_entries(&vault.root)))
+}
@@ -1,104 +1,106 @@
 use chrono::{Local, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{
     fs,
     path::{Component, Path, PathBuf},
-    time::{SystemTime, UNIX_EPOCH},
 };
+use tauri::State;
 use uuid::Uuid;
 use walkdir::WalkDir;
 
-const VAULT_VERSION: u32 = 1;
+use crate::{
+    error::{VaultError, VaultResult},
+    frontmatter::{Note, NoteMeta},
+    history,
+    journal::{self, Step},
+    lock::LockInfo,
+    moves::{move_file, numbered_variant, CollisionPolicy},
+    naming,
+    periodic::PeriodKind,
+    settings,
+    state::{OpenVault, VaultState},
+};
 
-fn homebase_vault_root() -> Result<PathBuf, String> {
-    let home = dirs::home_dir().ok_or_else(|| "Failed to determine home directory".to_string())?;
-    Ok(home.join("Homebase"))
-}
+pub(crate) const VAULT_VERSION: u32 = 2;
 
-fn validate_relative_path(path: &str) -> Result<PathBuf, String> {
+pub(crate) fn validate_relative_path(path: &str) -> VaultResult<PathBuf> {
     let rel = PathBuf::from(path);
     if rel.is_absolute() {
-        return Err("Path must be relative".to_string());
+        return Err(VaultError::invalid_path(path, "must be relative"));
     }
     for component in rel.components() {
         if matches!(component, Component::ParentDir) {
-            return Err("Path must not contain '..'".to_string());
+            return Err(VaultError::invalid_path(path, "must not contain '..'"));
         }
     }
     Ok(rel)
 }
 
-fn resolve_vault_path(relative_path: &str) -> Result<PathBuf, String> {
-    let root = homebase_vault_root()?;
-    let rel = validate_relative_path(relative_path)?;
-    Ok(root.join(rel))
-}
-
-fn ensure_vault_structure(vault_root: &Path) -> Result<(), String> {
+pub(crate) fn ensure_vault_structure(vault_root: &Path) -> VaultResult<()> {
     let dirs_to_create = [
         vault_root.join("notes/inbox"),
         vault_root.join("notes/archive"),
         vault_root.join("notes/daily"),
+        vault_root.join("notes/weekly"),
+        vault_root.join("notes/monthly"),
+        vault_root.join("notes/quarterly"),
+        vault_root.join("notes/yearly"),
         vault_root.join("notes/folders"),
         vault_root.join("notes/projects"),
         vault_root.join("assets"),
         vault_root.join("config"),
+        vault_root.join("config/templates"),
         vault_root.join(".homebase"),
     ];
 
     for dir in dirs_to_create {
-        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        fs::create_dir_all(&dir).map_err(|e| VaultError::io(&dir, e))?;
     }
 
     let settings_path = vault_root.join("config/settings.json");
     if !settings_path.exists() {
         let default_settings = serde_json::json!({
             "version": VAULT_VERSION,
-            "vaultPath": vault_root.to_string_lossy(),
             "createdAt": Utc::now().to_rfc3339(),
         });
-        write_atomic(&settings_path, &serde_json::to_string_pretty(&default_settings).unwrap())?;
+        write_atomic(
+            &settings_path,
+            serde_json::to_string_pretty(&default_settings).unwrap(),
+        )?;
     }
 
     Ok(())
 }
 
-fn write_atomic(path: &Path, contents: &str) -> Result<(), String> {
+pub(crate) fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> VaultResult<()> {
     let parent = path
         .parent()
-        .ok_or_else(|| "Invalid path (missing parent)".to_string())?;
-    fs::create_dir_all(parent)
-        .map_err(|e| format!("Failed to create directory {:?}: {}", parent, e))?;
+        .ok_or_else(|| VaultError::invalid_path(path, "missing parent"))?;
+    fs::create_dir_all(parent).map_err(|e| VaultError::io(parent, e))?;
 
     let tmp_path = parent.join(format!(
         ".{}.tmp",
-        path.file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string()
+        path.file_name().unwrap_or_default().to_string_lossy()
     ));
     let tmp_path = tmp_path.with_extension(format!("tmp-{}", Uuid::new_v4()));
 
-    fs::write(&tmp_path, contents)
-        .map_err(|e| format!("Failed to write temp file {:?}: {}", tmp_path, e))?;
+    fs::write(&tmp_path, contents).map_err(|e| VaultError::io(&tmp_path, e))?;
 
     if path.exists() {
-        fs::remove_file(path).map_err(|e| format!("Failed to replace {:?}: {}", path, e))?;
+        fs::remove_file(path).map_err(|e| VaultError::io(path, e))?;
     }
-    fs::rename(&tmp_path, path).map_err(|e| format!("Failed to rename {:?}: {}", tmp_path, e))?;
+    fs::rename(&tmp_path, path).map_err(|e| VaultError::io(&tmp_path, e))?;
     Ok(())
 }
 
-fn path_to_forward_slashes(path: &Path) -> String {
-    path.to_string_lossy().replace('\\', "/")
+/// Hex SHA-256 of a note's bytes; the version token for optimistic writes.
+pub(crate) fn content_hash(contents: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(contents))
 }
 
-fn relative_from_vault_root(full_path: &Path) -> Result<String, String> {
-    let vault_root = homebase_vault_root()?;
-    let rel = full_path
-        .strip_prefix(&vault_root)
-        .map_err(|_| "Path is outside vault".to_string())?;
-    Ok(path_to_forward_slashes(rel))
+pub(crate) fn path_to_forward_slashes(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
 }
 
 #[derive(Serialize)]
@@ -106,16 +108,13 @@ fn relative_from_vault_root(full_path: &Path) -> Result<String, String> {
 pub struct VaultInfo {
     pub vault_path: String,
     pub version: u32,
+    pub read_only: bool,
+    pub lock_holder: Option<LockInfo>,
 }
 
 #[tauri::command]
-pub fn vault_init() -> Result<VaultInfo, String> {
-    let vault_root = homebase_vault_root()?;
-    ensure_vault_structure(&vault_root)?;
-    Ok(VaultInfo {
-        vault_path: vault_root.to_string_lossy().to_string(),
-        version: VAULT_VERSION,
-    })
+pub fn vault_init(state: State<'_, VaultState>) -> VaultResult<VaultInfo> {
+    state.open_active()
 }
 
 #[derive(Serialize)]
@@ -127,7 +126,7 @@ pub struct VaultNoteEntry {
     pub size: u64,
 }
 
-fn kind_from_relative_path(relative_path: &str) -> String {
+pub(crate) fn kind_from_relative_path(relative_path: &str) -> String {
     if relative_path.starts_with("notes/inbox/") {
         return "inbox".to_string();
     }
@@ -137,6 +136,9 @@ fn kind_from_relative_path(relative_path: &str) -> String {
     if relative_path.starts_with("notes/daily/") {
         return "daily".to_string();
     }
+    if PeriodKind::from_relative_path(relative_path).is_some() {
+        return "periodic".to_string();
+    }
     if relative_path.starts_with("notes/projects/") {
         return "project".to_string();
     }
@@ -147,60 +149,176 @@ fn kind_from_relative_path(relative_path: &str) -> String {
 }
 
 #[tauri::command]
-pub fn vault_list_notes(include_archived: bool) -> Result<Vec<VaultNoteEntry>, String> {
-    let vault_root = homebase_vault_root()?;
-    ensure_vault_structure(&vault_root)?;
+pub fn vault_list_notes(
+    state: State<'_, VaultState>,
+    include_archived: bool,
+) -> VaultResult<Vec<VaultNoteEntry>> {
+    state.with_vault(|vault| Ok(vault.index.list_notes(include_archived)))
+}
 
-    let notes_root = vault_root.join("notes");
-    let mut entries: Vec<VaultNoteEntry> = Vec::new();
+/// Catches the index up with changes made outside the app. The list commands
+/// answer from memory, so the frontend calls this when it wants to see them,
+/// e.g. when the window regains focus.
+#[tauri::command]
+pub fn vault_refresh_index(state: State<'_, VaultState>) -> VaultResult<()> {
+    state.with_vault(|vault| vault.sync_index())
+}
 
-    for entry in WalkDir::new(&notes_root)
-        .follow_links(false)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        if !entry.file_type().is_file() {
-            continue;
-        }
-        let path = entry.path();
-        if path.extension().and_then(|e| e.to_str()) != Some("md") {
-            continue;
-        }
+#[tauri::command]
+pub fn vault_read_note(state: State<'_, VaultState>, relative_path: String) -> VaultResult<String> {
+    state.with_vault(|vault| {
+        let full = vault.resolve(&relative_path)?;
+        fs::read_to_string(&full).map_err(|e| VaultError::io(Path::new(&relative_path), e))
+    })
+}
 
-        let rel = relative_from_vault_root(path)?;
-        if !include_archived && rel.starts_with("notes/archive/") {
-            continue;
-        }
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteById {
+    pub relative_path: String,
+    pub contents: String,
+}
+
+#[tauri::command]
+pub fn vault_resolve_note_id(state: State<'_, VaultState>, id: String) -> VaultResult<String> {
+    state.with_vault(|vault| vault.resolve_note_id(&id))
+}
+
+#[tauri::command]
+pub fn vault_read_note_by_id(state: State<'_, VaultState>, id: String) -> VaultResult<NoteById> {
+    state.with_vault(|vault| {
+        let relative_path = vault.resolve_note_id(&id)?;
+        let full = vault.root.join(&relative_path);
+        let contents = fs::read_to_string(&full).map_err(|e| VaultError::io(&relative_path, e))?;
+        Ok(NoteById {
+            relative_path,
+            contents,
+        })
+    })
+}
 
-        let meta = fs::metadata(path).map_err(|e| format!("Failed to stat {:?}: {}", path, e))?;
-        let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
-        let mtime_ms = modified
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as i64;
-
-        entries.push(VaultNoteEntry {
-            relative_path: rel.clone(),
-            kind: kind_from_relative_path(&rel),
-            mtime_ms,
-            size: meta.len(),
+/// Rewrites a note's frontmatter in place. Notes whose frontmatter does not
+/// parse are left alone, since re-rendering them would drop what they contain.
+pub(crate) fn update_note_meta(full: &Path, update: impl FnOnce(&mut NoteMeta)) -> VaultResult<()> {
+    let contents = fs::read_to_string(full).map_err(|e| VaultError::io(full, e))?;
+    let Ok(mut note) = Note::parse(&contents) else {
+        return Ok(());
+    };
+    update(&mut note.meta);
+    write_atomic(full, &note.render().map_err(VaultError::internal)?)
+}
+
+/// Validates that `relative_path` is a note under `notes/` and parses it.
+pub(crate) fn read_parsed_note(
+    vault: &OpenVault,
+    relative_path: &str,
+) -> VaultResult<(String, Note)> {
+    let rel = path_to_forward_slashes(&validate_relative_path(relative_path)?);
+    if !rel.starts_with("notes/") || !rel.ends_with(".md") {
+        return Err(VaultError::invalid_path(
+            &rel,
+            "must be a note under notes/",
+        ));
+    }
+    let full = vault.root.join(&rel);
+    if !full.is_file() {
+        return Err(VaultError::not_found(&rel));
+    }
+    let contents = fs::read_to_string(&full).map_err(|e| VaultError::io(&rel, e))?;
+    let note = Note::parse(&contents).map_err(|e| VaultError::invalid_data(&rel, e))?;
+    Ok((rel, note))
+}
+
+/// `update_note_meta` as part of a structural change: the rewrite is journaled
+/// so undoing the change also puts the old frontmatter back.
+pub(crate) fn update_note_meta_recorded(
+    vault: &mut OpenVault,
+    rel: &str,
+    update: impl FnOnce(&mut NoteMeta),
+) -> VaultResult<()> {
+    let full = vault.root.join(rel);
+    let before = fs::read_to_string(&full).map_err(|e| VaultError::io(rel, e))?;
+    update_note_meta(&full, update)?;
+    let after = fs::read_to_string(&full).map_err(|e| VaultError::io(rel, e))?;
+    if after != before {
+        vault.record_step(Step::Write {
+            path: rel.to_string(),
+            before,
+            after,
         });
     }
+    Ok(())
+}
 
-    entries.sort_by(|a, b| b.mtime_ms.cmp(&a.mtime_ms));
-    Ok(entries)
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteNoteResult {
+    /// Differs from the path written to when auto-rename moved the note.
+    pub relative_path: String,
+    pub hash: String,
+}
+
+fn write_note(vault: &mut OpenVault, full: &Path, contents: &str) -> VaultResult<WriteNoteResult> {
+    history::record(&vault.root, full, contents, false)?;
+    write_atomic(full, contents)?;
+    let rel = vault.relative(full)?;
+    vault.index.refresh_note(&vault.root, &rel);
+    let relative_path = naming::auto_rename(vault, &rel, contents)?;
+    // The edit itself is not undoable here (history covers that), but the
+    // rename that followed the title is.
+    journal::commit(vault, "Rename note");
+    Ok(WriteNoteResult {
+        relative_path,
+        hash: content_hash(contents.as_bytes()),
+    })
 }
 
+/// Writes the note only if it still matches `expected_hash` (the hash of the
+/// contents the caller loaded), and returns the hash of the new contents. A
+/// missing file is `NotFound`, unless `expected_hash` is empty, which creates
+/// it.
 #[tauri::command]
-pub fn vault_read_note(relative_path: String) -> Result<String, String> {
-    let full = resolve_vault_path(&relative_path)?;
-    fs::read_to_string(&full).map_err(|e| format!("Failed to read {:?}: {}", full, e))
+pub fn vault_write_note(
+    state: State<'_, VaultState>,
+    relative_path: String,
+    contents: String,
+    expected_hash: String,
+) -> VaultResult<WriteNoteResult> {
+    state.with_writable_vault(|vault| {
+        let full = vault.resolve(&relative_path)?;
+        let current = match fs::read(&full) {
+            Ok(bytes) => Some(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(VaultError::io(&relative_path, e)),
+        };
+        let Some(current) = current else {
+            if expected_hash.is_empty() {
+                return write_note(vault, &full, &contents);
+            }
+            return Err(VaultError::not_found(&relative_path));
+        };
+        // A retry of a write that already landed is not a conflict.
+        if content_hash(&current) == expected_hash || current == contents.as_bytes() {
+            return write_note(vault, &full, &contents);
+        }
+        Err(VaultError::Conflict {
+            path: relative_path,
+            current_contents: Some(String::from_utf8_lossy(&current).into_owned()),
+        })
+    })
 }
 
+/// Overwrites the note regardless of what is on disk, for explicit "keep mine".
 #[tauri::command]
-pub fn vault_write_note(relative_path: String, contents: String) -> Result<(), String> {
-    let full = resolve_vault_path(&relative_path)?;
-    write_atomic(&full, &contents)
+pub fn vault_force_write_note(
+    state: State<'_, VaultState>,
+    relative_path: String,
+    contents: String,
+) -> VaultResult<WriteNoteResult> {
+    state.with_writable_vault(|vault| {
+        let full = vault.resolve(&relative_path)?;
+        write_note(vault, &full, &contents)
+    })
 }
 
 #[derive(Serialize)]
@@ -220,58 +338,57 @@ pub struct CreateNoteFromMarkdownArgs {
     pub contents: String,
 }
 
-#[tauri::command]
-pub fn vault_create_note_from_markdown(args: CreateNoteFromMarkdownArgs) -> Result<String, String> {
-    let vault_root = homebase_vault_root()?;
-    ensure_vault_structure(&vault_root)?;
-
-    let id = args.id.trim();
-    if id.is_empty() {
-        return Err("id is required".to_string());
-    }
-
-    let date = Local::now().format("%Y-%m-%d").to_string();
-    let id_short: String = id.chars().filter(|c| *c != '-').take(8).collect();
-    let id_short = if id_short.is_empty() {
-        &Uuid::new_v4().simple().to_string()[..8]
-    } else {
-        id_short.as_str()
-    };
-
-    let slug = args
-        .title_hint
-        .as_deref()
-        .map(slugify)
-        .unwrap_or_else(|| "note".to_string());
-    let file_name = format!("{}-{}-{}.md", date, slug, id_short);
-
-    let target_dir = args.target_dir.unwrap_or_else(|| "notes/inbox".to_string());
-    let target_dir_rel = validate_relative_path(&target_dir)?;
+pub(crate) fn validate_note_target_dir(target_dir: &str) -> VaultResult<String> {
+    let target_dir_rel = validate_relative_path(target_dir)?;
     let target_dir_str = path_to_forward_slashes(&target_dir_rel);
     if !target_dir_str.starts_with("notes/inbox")
         && !target_dir_str.starts_with("notes/daily")
         && !target_dir_str.starts_with("notes/folders")
         && !target_dir_str.starts_with("notes/projects")
     {
-        return Err("Target directory must be under notes/inbox, notes/daily, notes/folders, or notes/projects"
-            .to_string());
+        return Err(VaultError::invalid_path(
+            target_dir,
+            "must be under notes/inbox, notes/daily, notes/folders, or notes/projects",
+        ));
     }
-
-    let rel_path = format!("{}/{}", target_dir_str.trim_end_matches('/'), file_name);
-    let full_path = vault_root.join(&rel_path);
-    if full_path.exists() {
-        return Err("Note file already exists".to_string());
-    }
-
-    write_atomic(&full_path, &args.contents)?;
-    Ok(rel_path)
+    Ok(target_dir_str)
 }
 
 #[tauri::command]
-pub fn vault_create_note(target_dir: Option<String>) -> Result<CreateNoteResult, String> {
-    let vault_root = homebase_vault_root()?;
-    ensure_vault_structure(&vault_root)?;
+pub fn vault_create_note_from_markdown(
+    state: State<'_, VaultState>,
+    args: CreateNoteFromMarkdownArgs,
+) -> VaultResult<String> {
+    state.with_writable_vault(|vault| {
+        let id = args.id.trim();
+        if id.is_empty() {
+            return Err(VaultError::invalid_input("id", "is required"));
+        }
 
+        let date = Local::now().format("%Y-%m-%d").to_string();
+        let slug = args
+            .title_hint
+            .as_deref()
+            .map(naming::note_slug)
+            .unwrap_or_else(|| "note".to_string());
+        let file_name = format!("{}-{}-{}.md", date, slug, naming::id_short(id));
+
+        let target_dir = args.target_dir.as_deref().unwrap_or("notes/inbox");
+        let target_dir_str = validate_note_target_dir(target_dir)?;
+
+        let rel_path = format!("{}/{}", target_dir_str.trim_end_matches('/'), file_name);
+        let full_path = vault.root.join(&rel_path);
+        if full_path.exists() {
+            return Err(VaultError::already_exists(&rel_path));
+        }
+
+        write_atomic(&full_path, &args.contents)?;
+        vault.index.refresh_note(&vault.root, &rel_path);
+        Ok(rel_path)
+    })
+}
+
+fn create_note(vault: &mut OpenVault, target_dir: Option<String>) -> VaultResult<CreateNoteResult> {
     let id = Uuid::new_v4();
     let now_iso = Utc::now().to_rfc3339();
     let date = Local::now().format("%Y-%m-%d").to_string();
@@ -279,27 +396,18 @@ pub fn vault_create_note(target_dir: Option<String>) -> Result<CreateNoteResult,
     let short = &short[..8];
     let file_name = format!("{}-untitled-{}.md", date, short);
     let target_dir = target_dir.unwrap_or_else(|| "notes/inbox".to_string());
-    let target_dir_rel = validate_relative_path(&target_dir)?;
-    let target_dir_str = path_to_forward_slashes(&target_dir_rel);
-    if !target_dir_str.starts_with("notes/inbox")
-        && !target_dir_str.starts_with("notes/daily")
-        && !target_dir_str.starts_with("notes/folders")
-        && !target_dir_str.starts_with("notes/projects")
-    {
-        return Err("Target directory must be under notes/inbox, notes/daily, notes/folders, or notes/projects"
-            .to_string());
-    }
+    let target_dir_str = validate_note_target_dir(&target_dir)?;
 
     let rel_path = format!("{}/{}", target_dir_str.trim_end_matches('/'), file_name);
     let user_placed = !rel_path.starts_with("notes/inbox/");
 
-    let contents = format!(
-        "---\nid: {}\ncreated: {}\nmodified: {}\nprojects: []\ntopics: []\nuser_placed: {}\n---\n\n",
-        id, now_iso, now_iso, user_placed
-    );
+    let mut meta = NoteMeta::new(id.to_string(), &now_iso);
+    meta.user_placed = Some(user_placed);
+    let contents = Note::new(meta, "").render().map_err(VaultError::internal)?;
 
-    let full_path = vault_root.join(&rel_path);
+    let full_path = vault.root.join(&rel_path);
     write_atomic(&full_path, &contents)?;
+    vault.index.refresh_note(&vault.root, &rel_path);
 
     Ok(CreateNoteResult {
         id: id.to_string(),
@@ -309,96 +417,202 @@ pub fn vault_create_note(target_dir: Option<String>) -> Result<CreateNoteResult,
 }
 
 #[tauri::command]
-pub fn vault_create_note_in_inbox() -> Result<CreateNoteResult, String> {
-    vault_create_note(None)
-}
-
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct CreateDailyNoteArgs {
-    pub date: String,
-    pub contents: Option<String>,
+pub fn vault_create_note(
+    state: State<'_, VaultState>,
+    target_dir: Option<String>,
+) -> VaultResult<CreateNoteResult> {
+    state.with_writable_vault(|vault| create_note(vault, target_dir))
 }
 
 #[tauri::command]
-pub fn vault_create_daily_note(args: CreateDailyNoteArgs) -> Result<String, String> {
-    let vault_root = homebase_vault_root()?;
-    ensure_vault_structure(&vault_root)?;
-
-    let date = args.date.trim();
-    if date.len() != 10 || date.chars().nth(4) != Some('-') || date.chars().nth(7) != Some('-') {
-        return Err("Date must be in YYYY-MM-DD format".to_string());
-    }
-    if !date
-        .chars()
-        .filter(|c| *c != '-')
-        .all(|c| c.is_ascii_digit())
-    {
-        return Err("Date must be in YYYY-MM-DD format".to_string());
-    }
-
-    let rel_path = format!("notes/daily/{}.md", date);
-    let full_path = vault_root.join(&rel_path);
-    if full_path.exists() {
-        return Ok(rel_path);
-    }
-
-    let now_iso = Utc::now().to_rfc3339();
-    let id = format!("daily-{}", date);
-    let body = args.contents.unwrap_or_default();
-
-    let contents = format!(
-        "---\nid: {}\ncreated: {}\nmodified: {}\nprojects: []\ntopics: []\nuser_placed: true\ndaily_date: {}\n---\n\n{}",
-        id, now_iso, now_iso, date, body
-    );
-
-    write_atomic(&full_path, &contents)?;
-    Ok(rel_path)
+pub fn vault_create_note_in_inbox(state: State<'_, VaultState>) -> VaultResult<CreateNoteResult> {
+    state.with_writable_vault(|vault| create_note(vault, None))
 }
 
-#[tauri::command]
-pub fn vault_archive_note(relative_path: String) -> Result<String, String> {
-    let vault_root = homebase_vault_root()?;
-    ensure_vault_structure(&vault_root)?;
-
-    let rel = validate_relative_path(&relative_path)?;
-    let source = vault_root.join(&rel);
-    if !source.exists() {
-        return Err("Note does not exist".to_string());
+/// Validates an archive request; returns the note's path and where it would
+/// be archived to (the same path when it already is archived).
+pub(crate) fn archive_target(
+    vault: &OpenVault,
+    relative_path: &str,
+) -> VaultResult<(String, String)> {
+    let rel_str = path_to_forward_slashes(&validate_relative_path(relative_path)?);
+    if !vault.root.join(&rel_str).exists() {
+        return Err(VaultError::not_found(relative_path));
     }
-    let rel_str = path_to_forward_slashes(&rel);
     if rel_str.starts_with("notes/archive/") {
-        return Ok(rel_str);
+        return Ok((rel_str.clone(), rel_str));
     }
     if !rel_str.starts_with("notes/") {
-        return Err("Can only archive notes under notes/".to_string());
+        return Err(VaultError::invalid_path(
+            &rel_str,
+            "can only archive notes under notes/",
+        ));
+    }
+    let target_rel = format!("notes/archive/{}", rel_str.trim_start_matches("notes/"));
+    Ok((rel_str, target_rel))
+}
+
+pub(crate) fn archive_note(
+    vault: &mut OpenVault,
+    relative_path: &str,
+    policy: CollisionPolicy,
+) -> VaultResult<String> {
+    let (rel_str, target_rel) = archive_target(vault, relative_path)?;
+    if target_rel == rel_str {
+        return Ok(rel_str);
     }
+    let target_rel = claim_note_target(vault, target_rel, policy)?;
+
+    move_file(&vault.root.join(&rel_str), &vault.root.join(&target_rel))?;
+    vault.record_step(Step::Move {
+        from: rel_str.clone(),
+        to: target_rel.clone(),
+    });
+    update_note_meta_recorded(vault, &target_rel, |meta| {
+        meta.archived_at = Some(Utc::now().to_rfc3339());
+        meta.archived_from = Some(rel_str.clone());
+    })?;
+    vault.index.move_note(&vault.root, &rel_str, &target_rel);
+    Ok(target_rel)
+}
 
-    let rel_under_notes = rel_str.trim_start_matches("notes/");
-    let target_rel = format!("notes/archive/{}", rel_under_notes);
-    let target = vault_root.join(&target_rel);
+#[tauri::command]
+pub fn vault_archive_note(
+    state: State<'_, VaultState>,
+    relative_path: String,
+    on_collision: Option<CollisionPolicy>,
+) -> VaultResult<String> {
+    state.with_writable_vault(|vault| {
+        let policy = collision_policy(vault, on_collision)?;
+        let target_rel = archive_note(vault, &relative_path, policy)?;
+        journal::commit(vault, "Archive note");
+        Ok(target_rel)
+    })
+}
 
-    if let Some(parent) = target.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+pub(crate) fn collision_policy(
+    vault: &OpenVault,
+    on_collision: Option<CollisionPolicy>,
+) -> VaultResult<CollisionPolicy> {
+    match on_collision {
+        Some(policy) => Ok(policy),
+        None => Ok(settings::load(&vault.root)?.collision_policy),
+    }
+}
+
+/// Decides where a note headed for `target_rel` actually lands, making room
+/// first when the policy is `KeepBoth`.
+fn claim_note_target(
+    vault: &mut OpenVault,
+    target_rel: String,
+    policy: CollisionPolicy,
+) -> VaultResult<String> {
+    if !vault.root.join(&target_rel).exists() {
+        return Ok(target_rel);
+    }
+    match policy {
+        CollisionPolicy::Fail => Err(VaultError::already_exists(&target_rel)),
+        CollisionPolicy::AutoSuffix => Ok(numbered_variant(&vault.root, &target_rel)),
+        CollisionPolicy::KeepBoth => {
+            let aside = numbered_variant(&vault.root, &target_rel);
+            move_file(&vault.root.join(&target_rel), &vault.root.join(&aside))?;
+            vault.record_step(Step::Move {
+                from: target_rel.clone(),
+                to: aside.clone(),
+            });
+            vault.index.move_note(&vault.root, &target_rel, &aside);
+            Ok(target_rel)
+        }
     }
+}
 
-    fs::rename(&source, &target).map_err(|e| format!("Failed to archive note: {}", e))?;
-    Ok(target_rel)
+/// Where an archived note goes back to: `archived_from` when it is a sane
+/// non-archive note path, otherwise the archive path with `archive/` stripped
+/// (notes archived before the field existed).
+fn unarchive_target(rel: &str, meta: &NoteMeta) -> String {
+    meta.archived_from
+        .as_deref()
+        .filter(|from| {
+            from.starts_with("notes/")
+                && !from.starts_with("notes/archive/")
+                && from.ends_with(".md")
+                && validate_relative_path(from).is_ok()
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("notes/{}", rel.trim_start_matches("notes/archive/")))
 }
 
 #[tauri::command]
-pub fn vault_move_note(relative_path: String, target_dir: String) -> Result<String, String> {
-    let vault_root = homebase_vault_root()?;
-    ensure_vault_structure(&vault_root)?;
+pub fn vault_unarchive_note(
+    state: State<'_, VaultState>,
+    relative_path: String,
+    on_collision: Option<CollisionPolicy>,
+) -> VaultResult<String> {
+    state.with_writable_vault(|vault| {
+        let rel_str = path_to_forward_slashes(&validate_relative_path(&relative_path)?);
+        if !rel_str.starts_with("notes/archive/") {
+            return Err(VaultError::invalid_path(
+                &rel_str,
+                "is not an archived note",
+            ));
+        }
+        let source = vault.root.join(&rel_str);
+        let contents = fs::read_to_string(&source).map_err(|e| VaultError::io(&rel_str, e))?;
+        let meta = Note::parse(&contents).map(|n| n.meta).unwrap_or_default();
+
+        let mut target_rel = unarchive_target(&rel_str, &meta);
+        // Inbox and daily always exist; a deleted folder or project does not come
+        // back just because one of its notes did.
+        let target_parent = Path::new(&target_rel)
+            .parent()
+            .map(|p| vault.root.join(p))
+            .unwrap_or_else(|| vault.root.join("notes/inbox"));
+        let fell_back = !target_parent.is_dir()
+            && !target_rel.starts_with("notes/inbox/")
+            && !target_rel.starts_with("notes/daily/");
+        if fell_back {
+            let file_name = Path::new(&target_rel)
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            target_rel = format!("notes/inbox/{}", file_name);
+        }
 
-    let rel = validate_relative_path(&relative_path)?;
-    let src = vault_root.join(&rel);
+        let policy = collision_policy(vault, on_collision)?;
+        let target_rel = claim_note_target(vault, target_rel, policy)?;
+        move_file(&source, &vault.root.join(&target_rel))?;
+        vault.record_step(Step::Move {
+            from: rel_str.clone(),
+            to: target_rel.clone(),
+        });
+        update_note_meta_recorded(vault, &target_rel, |meta| {
+            meta.archived_at = None;
+            meta.archived_from = None;
+            if fell_back {
+                meta.user_placed = Some(false);
+            }
+        })?;
+        vault.index.move_note(&vault.root, &rel_str, &target_rel);
+        journal::commit(vault, "Unarchive note");
+        Ok(target_rel)
+    })
+}
+
+/// Validates a move request; returns the note's path and the path it would
+/// get in `target_dir` (the same path when it is already there).
+pub(crate) fn move_target(
+    vault: &OpenVault,
+    relative_path: &str,
+    target_dir: &str,
+) -> VaultResult<(String, String)> {
+    let rel = validate_relative_path(relative_path)?;
+    let src = vault.root.join(&rel);
     if !src.exists() {
-        return Err("Note does not exist".to_string());
+        return Err(VaultError::not_found(relative_path));
     }
 
-    let target_dir_rel = validate_relative_path(&target_dir)?;
+    let target_dir_rel = validate_relative_path(target_dir)?;
     let target_dir_str = path_to_forward_slashes(&target_dir_rel);
     if !target_dir_str.starts_with("notes/inbox/")
         && target_dir_str != "notes/inbox"
@@ -409,108 +623,164 @@ pub fn vault_move_note(relative_path: String, target_dir: String) -> Result<Stri
         && !target_dir_str.starts_with("notes/projects/")
         && target_dir_str != "notes/projects"
     {
-        return Err("Target directory must be under notes/inbox, notes/daily, notes/folders, or notes/projects"
-            .to_string());
+        return Err(VaultError::invalid_path(
+            target_dir,
+            "must be under notes/inbox, notes/daily, notes/folders, or notes/projects",
+        ));
     }
 
     let file_name = src
         .file_name()
-        .ok_or_else(|| "Invalid source path".to_string())?;
-    let dest_dir = vault_root.join(&target_dir_rel);
-    fs::create_dir_all(&dest_dir)
-        .map_err(|e| format!("Failed to create {:?}: {}", dest_dir, e))?;
-    let dest = dest_dir.join(file_name);
+        .ok_or_else(|| VaultError::invalid_path(relative_path, "missing file name"))?;
+    let dest_rel = vault.relative(&vault.root.join(&target_dir_rel).join(file_name))?;
+    Ok((path_to_forward_slashes(&rel), dest_rel))
+}
+
+pub(crate) fn move_note(
+    vault: &mut OpenVault,
+    relative_path: &str,
+    target_dir: &str,
+    policy: CollisionPolicy,
+) -> VaultResult<String> {
+    let (rel_str, dest_rel) = move_target(vault, relative_path, target_dir)?;
+    if dest_rel == rel_str {
+        return Ok(dest_rel);
+    }
+    let dest_rel = claim_note_target(vault, dest_rel, policy)?;
 
-    fs::rename(&src, &dest).map_err(|e| format!("Failed to move note: {}", e))?;
-    relative_from_vault_root(&dest)
+    move_file(&vault.root.join(&rel_str), &vault.root.join(&dest_rel))?;
+    vault.record_step(Step::Move {
+        from: rel_str.clone(),
+        to: dest_rel.clone(),
+    });
+    vault.index.move_note(&vault.root, &rel_str, &dest_rel);
+    Ok(dest_rel)
 }
 
 #[tauri::command]
-pub fn vault_list_folders() -> Result<Vec<String>, String> {
-    let vault_root = homebase_vault_root()?;
-    ensure_vault_structure(&vault_root)?;
-
-    let root = vault_root.join("notes/folders");
-    let mut folders: Vec<String> = Vec::new();
-    for entry in WalkDir::new(&root)
-        .min_depth(1)
-        .follow_links(false)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        if !entry.file_type().is_dir() {
-            continue;
-        }
-        folders.push(relative_from_vault_root(entry.path())?);
-    }
-    folders.sort();
-    Ok(folders)
+pub fn vault_move_note(
+    state: State<'_, VaultState>,
+    relative_path: String,
+    target_dir: String,
+    on_collision: Option<CollisionPolicy>,
+) -> VaultResult<String> {
+    state.with_writable_vault(|vault| {
+        let policy = collision_policy(vault, on_collision)?;
+        let dest_rel = move_note(vault, &relative_path, &target_dir, policy)?;
+        journal::commit(vault, "Move note");
+        Ok(dest_rel)
+    })
 }
 
 #[tauri::command]
-pub fn vault_create_folder(relative_path: String) -> Result<(), String> {
-    let vault_root = homebase_vault_root()?;
-    ensure_vault_structure(&vault_root)?;
-
-    let rel = validate_relative_path(&relative_path)?;
-    let rel_str = path_to_forward_slashes(&rel);
-    if !rel_str.starts_with("notes/folders/") {
-        return Err("Folder path must start with notes/folders/".to_string());
-    }
-    let full = vault_root.join(rel);
-    fs::create_dir_all(&full).map_err(|e| format!("Failed to create folder: {}", e))?;
-    Ok(())
+pub fn vault_list_folders(state: State<'_, VaultState>) -> VaultResult<Vec<String>> {
+    state.with_vault(|vault| Ok(vault.index.list_folders()))
 }
 
 #[tauri::command]
-pub fn vault_rename_folder(from_relative_path: String, to_name: String) -> Result<String, String> {
-    let vault_root = homebase_vault_root()?;
-    ensure_vault_structure(&vault_root)?;
-
-    let from_rel = validate_relative_path(&from_relative_path)?;
-    let from_rel_str = path_to_forward_slashes(&from_rel);
-    if !from_rel_str.starts_with("notes/folders/") {
-        return Err("Folder path must start with notes/folders/".to_string());
-    }
+pub fn vault_create_folder(state: State<'_, VaultState>, relative_path: String) -> VaultResult<()> {
+    state.with_writable_vault(|vault| {
+        let rel = validate_relative_path(&relative_path)?;
+        let rel_str = path_to_forward_slashes(&rel);
+        if !rel_str.starts_with("notes/folders/") {
+            return Err(VaultError::invalid_path(
+                &rel_str,
+                "must start with notes/folders/",
+            ));
+        }
+        let full = vault.root.join(rel);
+        let existed = full.is_dir();
+        fs::create_dir_all(&full).map_err(|e| VaultError::io(&rel_str, e))?;
+        let rel_str = rel_str.trim_end_matches('/');
+        vault.index.add_folder(rel_str);
+        if !existed {
+            vault.record_step(Step::CreateDir {
+                path: rel_str.to_string(),
+            });
+            journal::commit(vault, "Create folder");
+        }
+        Ok(())
+    })
+}
 
-    if to_name.trim().is_empty() {
-        return Err("New name cannot be empty".to_string());
-    }
-    if to_name.contains('/') || to_name.contains('\\') {
-        return Err("New name must not contain path separators".to_string());
-    }
-    if to_name.trim() == "." || to_name.trim() == ".." {
-        return Err("Invalid folder name".to_string());
-    }
+#[tauri::command]
+pub fn vault_rename_folder(
+    state: State<'_, VaultState>,
+    from_relative_path: String,
+    to_name: String,
+) -> VaultResult<String> {
+    state.with_writable_vault(|vault| {
+        let from_rel = validate_relative_path(&from_relative_path)?;
+        let from_rel_str = path_to_forward_slashes(&from_rel);
+        if !from_rel_str.starts_with("notes/folders/") {
+            return Err(VaultError::invalid_path(
+                &from_rel_str,
+                "must start with notes/folders/",
+            ));
+        }
 
-    let from_full = vault_root.join(&from_rel);
-    let parent = from_full
-        .parent()
-        .ok_or_else(|| "Invalid folder path".to_string())?;
-    let to_full = parent.join(&to_name);
+        if to_name.trim().is_empty() {
+            return Err(VaultError::invalid_input("name", "cannot be empty"));
+        }
+        if to_name.contains('/') || to_name.contains('\\') {
+            return Err(VaultError::invalid_input(
+                "name",
+                "must not contain path separators",
+            ));
+        }
+        if to_name.trim() == "." || to_name.trim() == ".." {
+            return Err(VaultError::invalid_input(
+                "name",
+                "is not a valid folder name",
+            ));
+        }
+
+        let from_full = vault.root.join(&from_rel);
+        let parent = from_full
+            .parent()
+            .ok_or_else(|| VaultError::invalid_path(&from_rel_str, "missing parent"))?;
+        let to_full = parent.join(&to_name);
 
-    fs::rename(&from_full, &to_full).map_err(|e| format!("Failed to rename folder: {}", e))?;
-    relative_from_vault_root(&to_full)
+        if to_full.exists() {
+            return Err(VaultError::already_exists(vault.relative(&to_full)?));
+        }
+        fs::rename(&from_full, &to_full).map_err(|e| VaultError::io(&from_rel_str, e))?;
+        let from_rel_str = vault.relative(&from_full)?;
+        let to_rel = vault.relative(&to_full)?;
+        vault.index.rename_dir(&from_rel_str, &to_rel);
+        vault.record_step(Step::Move {
+            from: from_rel_str,
+            to: to_rel.clone(),
+        });
+        journal::commit(vault, "Rename folder");
+        Ok(to_rel)
+    })
 }
 
 #[tauri::command]
-pub fn vault_delete_folder(relative_path: String) -> Result<(), String> {
-    let vault_root = homebase_vault_root()?;
-    ensure_vault_structure(&vault_root)?;
-
-    let rel = validate_relative_path(&relative_path)?;
-    let rel_str = path_to_forward_slashes(&rel);
-    if !rel_str.starts_with("notes/folders/") {
-        return Err("Folder path must start with notes/folders/".to_string());
-    }
+pub fn vault_delete_folder(state: State<'_, VaultState>, relative_path: String) -> VaultResult<()> {
+    state.with_writable_vault(|vault| {
+        let rel = validate_relative_path(&relative_path)?;
+        let rel_str = path_to_forward_slashes(&rel);
+        if !rel_str.starts_with("notes/folders/") {
+            return Err(VaultError::invalid_path(
+                &rel_str,
+                "must start with notes/folders/",
+            ));
+        }
 
-    let full = vault_root.join(rel);
-    let mut walk = WalkDir::new(&full).min_depth(1).into_iter();
-    if walk.next().is_some() {
-        return Err("Folder is not empty".to_string());
-    }
-    fs::remove_dir(&full).map_err(|e| format!("Failed to delete folder: {}", e))?;
-    Ok(())
+        let full = vault.root.join(rel);
+        let mut walk = WalkDir::new(&full).min_depth(1).into_iter();
+        if walk.next().is_some() {
+            return Err(VaultError::NotEmpty { path: rel_str });
+        }
+        fs::remove_dir(&full).map_err(|e| VaultError::io(&rel_str, e))?;
+        let rel_str = vault.relative(&full)?;
+        vault.index.remove_folder(&rel_str);
+        vault.record_step(Step::RemoveDir { path: rel_str });
+        journal::commit(vault, "Delete folder");
+        Ok(())
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -534,7 +804,24 @@ pub struct ProjectInfo {
     pub folder_relative_path: String,
 }
 
-fn slugify(input: &str) -> String {
+impl ProjectInfo {
+    fn new(meta: &ProjectMeta, folder_relative_path: String) -> Self {
+        ProjectInfo {
+            id: meta.id.clone(),
+            name: meta.name.clone(),
+            status: meta.status.clone(),
+            created: meta.created.clone(),
+            modified: meta.modified.clone(),
+            folder_relative_path,
+        }
+    }
+}
+
+pub(crate) fn slugify(input: &str) -> String {
+    slugify_or(input, "project")
+}
+
+pub(crate) fn slugify_or(input: &str, fallback: &str) -> String {
     let mut out = String::new();
     let mut last_was_dash = false;
     for ch in input.trim().chars() {
@@ -549,29 +836,31 @@ fn slugify(input: &str) -> String {
     }
     let out = out.trim_matches('-').to_string();
     if out.is_empty() {
-        "project".to_string()
+        fallback.to_string()
     } else {
         out
     }
 }
 
-fn read_project_meta(path: &Path) -> Result<ProjectMeta, String> {
-    let raw = fs::read_to_string(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
-    serde_json::from_str(&raw).map_err(|e| format!("Invalid JSON {:?}: {}", path, e))
+fn read_project_meta(path: &Path) -> VaultResult<ProjectMeta> {
+    let raw = fs::read_to_string(path).map_err(|e| VaultError::io(path, e))?;
+    serde_json::from_str(&raw).map_err(|e| VaultError::invalid_data(path, e))
 }
 
-fn write_project_meta(path: &Path, meta: &ProjectMeta) -> Result<(), String> {
-    let raw = serde_json::to_string_pretty(meta).map_err(|e| e.to_string())?;
+fn write_project_meta(path: &Path, meta: &ProjectMeta) -> VaultResult<()> {
+    let raw = serde_json::to_string_pretty(meta).map_err(VaultError::internal)?;
     write_atomic(path, &raw)
 }
 
-fn list_projects_internal(vault_root: &Path) -> Result<Vec<(PathBuf, ProjectMeta)>, String> {
+/// Every project folder with its parsed `.project.json`. A file that fails to
+/// read or parse is returned as an error for that project alone.
+pub(crate) fn list_projects_internal(
+    vault_root: &Path,
+) -> VaultResult<Vec<(PathBuf, VaultResult<ProjectMeta>)>> {
     let projects_root = vault_root.join("notes/projects");
-    let mut out: Vec<(PathBuf, ProjectMeta)> = Vec::new();
-    for entry in fs::read_dir(&projects_root)
-        .map_err(|e| format!("Failed to read {:?}: {}", projects_root, e))?
-    {
-        let entry = entry.map_err(|e| e.to_string())?;
+    let mut out: Vec<(PathBuf, VaultResult<ProjectMeta>)> = Vec::new();
+    for entry in fs::read_dir(&projects_root).map_err(|e| VaultError::io(&projects_root, e))? {
+        let entry = entry.map_err(|e| VaultError::io(&projects_root, e))?;
         let path = entry.path();
         if !path.is_dir() {
             continue;
@@ -580,71 +869,70 @@ fn list_projects_internal(vault_root: &Path) -> Result<Vec<(PathBuf, ProjectMeta
         if !meta_path.exists() {
             continue;
         }
-        let meta = read_project_meta(&meta_path)?;
+        let meta = read_project_meta(&meta_path);
         out.push((path, meta));
     }
     Ok(out)
 }
 
 #[tauri::command]
-pub fn vault_list_projects() -> Result<Vec<ProjectInfo>, String> {
-    let vault_root = homebase_vault_root()?;
-    ensure_vault_structure(&vault_root)?;
-
-    let mut projects: Vec<ProjectInfo> = Vec::new();
-    for (folder_path, meta) in list_projects_internal(&vault_root)? {
-        projects.push(ProjectInfo {
-            id: meta.id.clone(),
-            name: meta.name.clone(),
-            status: meta.status.clone(),
-            created: meta.created.clone(),
-            modified: meta.modified.clone(),
-            folder_relative_path: relative_from_vault_root(&folder_path)?,
-        });
-    }
-    projects.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-    Ok(projects)
+pub fn vault_list_projects(state: State<'_, VaultState>) -> VaultResult<Vec<ProjectInfo>> {
+    state.with_vault(|vault| {
+        let mut projects: Vec<ProjectInfo> = vault
+            .index
+            .projects()
+            .map(|p| ProjectInfo::new(&p.meta, p.folder_relative_path.clone()))
+            .collect();
+        projects.sort_by_key(|p| p.name.to_lowercase());
+        Ok(projects)
+    })
 }
 
 #[tauri::command]
-pub fn vault_create_project(name: String) -> Result<ProjectInfo, String> {
-    let vault_root = homebase_vault_root()?;
-    ensure_vault_structure(&vault_root)?;
-
-    let id = Uuid::new_v4().to_string();
-    let now = Utc::now().to_rfc3339();
-    let status = "active".to_string();
-
-    let base_slug = slugify(&name);
-    let short = &Uuid::new_v4().simple().to_string()[..6];
-    let mut folder_name = base_slug.clone();
+pub fn vault_create_project(
+    state: State<'_, VaultState>,
+    name: String,
+) -> VaultResult<ProjectInfo> {
+    state.with_writable_vault(|vault| {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now().to_rfc3339();
+        let status = "active".to_string();
+
+        let base_slug = slugify(&name);
+        let short = &Uuid::new_v4().simple().to_string()[..6];
+        let mut folder_name = base_slug.clone();
+
+        let projects_root = vault.root.join("notes/projects");
+        let mut folder_path = projects_root.join(&folder_name);
+        if folder_path.exists() {
+            folder_name = format!("{}-{}", base_slug, short);
+            folder_path = projects_root.join(&folder_name);
+        }
 
-    let projects_root = vault_root.join("notes/projects");
-    let mut folder_path = projects_root.join(&folder_name);
-    if folder_path.exists() {
-        folder_name = format!("{}-{}", base_slug, short);
-        folder_path = projects_root.join(&folder_name);
-    }
+        fs::create_dir_all(&folder_path).map_err(|e| VaultError::io(&folder_path, e))?;
+        let folder_relative_path = vault.relative(&folder_path)?;
+        vault.record_step(Step::CreateDir {
+            path: folder_relative_path.clone(),
+        });
 
-    fs::create_dir_all(&folder_path).map_err(|e| format!("Failed to create project: {}", e))?;
+        let meta = ProjectMeta {
+            id,
+            name,
+            status,
+            created: now.clone(),
+            modified: now,
+        };
+        let meta_path = folder_path.join(".project.json");
+        write_project_meta(&meta_path, &meta)?;
+        vault.record_step(Step::CreateFile {
+            path: vault.relative(&meta_path)?,
+            contents: fs::read_to_string(&meta_path).map_err(|e| VaultError::io(&meta_path, e))?,
+        });
 
-    let meta = ProjectMeta {
-        id: id.clone(),
-        name: name.clone(),
-        status: status.clone(),
-        created: now.clone(),
-        modified: now.clone(),
-    };
-    let meta_path = folder_path.join(".project.json");
-    write_project_meta(&meta_path, &meta)?;
-
-    Ok(ProjectInfo {
-        id,
-        name,
-        status,
-        created: now.clone(),
-        modified: now,
-        folder_relative_path: relative_from_vault_root(&folder_path)?,
+        let info = ProjectInfo::new(&meta, folder_relative_path.clone());
+        vault.index.upsert_project(folder_relative_path, meta);
+        journal::commit(vault, "Create project");
+        Ok(info)
     })
 }
 
@@ -657,49 +945,66 @@ pub struct UpdateProjectArgs {
 }
 
 #[tauri::command]
-pub fn vault_update_project(args: UpdateProjectArgs) -> Result<ProjectInfo, String> {
-    let vault_root = homebase_vault_root()?;
-    ensure_vault_structure(&vault_root)?;
-
-    let projects = list_projects_internal(&vault_root)?;
-    let (folder_path, mut meta) = projects
-        .into_iter()
-        .find(|(_, m)| m.id == args.id)
-        .ok_or_else(|| "Project not found".to_string())?;
-
-    if let Some(name) = args.name.clone() {
-        meta.name = name;
-    }
-    if let Some(status) = args.status.clone() {
-        meta.status = status;
-    }
-    meta.modified = Utc::now().to_rfc3339();
-
-    let mut final_folder_path = folder_path.clone();
-    if args.name.is_some() {
-        let projects_root = vault_root.join("notes/projects");
-        let desired = slugify(&meta.name);
-        let mut desired_path = projects_root.join(&desired);
-        if desired_path.exists() && desired_path != folder_path {
-            let short = &Uuid::new_v4().simple().to_string()[..6];
-            desired_path = projects_root.join(format!("{}-{}", desired, short));
+pub fn vault_update_project(
+    state: State<'_, VaultState>,
+    args: UpdateProjectArgs,
+) -> VaultResult<ProjectInfo> {
+    state.with_writable_vault(|vault| {
+        let project = vault
+            .index
+            .project(&args.id)
+            .ok_or_else(|| VaultError::IdNotFound {
+                kind: "project",
+                id: args.id.clone(),
+            })?;
+        let folder_path = vault.root.join(&project.folder_relative_path);
+        let mut meta = project.meta.clone();
+
+        if let Some(name) = args.name.clone() {
+            meta.name = name;
         }
-        if desired_path != folder_path {
-            fs::rename(&folder_path, &desired_path)
-                .map_err(|e| format!("Failed to rename project folder: {}", e))?;
-            final_folder_path = desired_path;
+        if let Some(status) = args.status.clone() {
+            meta.status = status;
+        }
+        meta.modified = Utc::now().to_rfc3339();
+
+        let mut final_folder_path = folder_path.clone();
+        if args.name.is_some() {
+            let projects_root = vault.root.join("notes/projects");
+            let desired = slugify(&meta.name);
+            let mut desired_path = projects_root.join(&desired);
+            if desired_path.exists() && desired_path != folder_path {
+                let short = &Uuid::new_v4().simple().to_string()[..6];
+                desired_path = projects_root.join(format!("{}-{}", desired, short));
+            }
+            if desired_path != folder_path {
+                fs::rename(&folder_path, &desired_path)
+                    .map_err(|e| VaultError::io(&folder_path, e))?;
+                let from_rel = vault.relative(&folder_path)?;
+                let to_rel = vault.relative(&desired_path)?;
+                vault.index.rename_dir(&from_rel, &to_rel);
+                vault.record_step(Step::Move {
+                    from: from_rel,
+                    to: to_rel,
+                });
+                final_folder_path = desired_path;
+            }
         }
-    }
 
-    let meta_path = final_folder_path.join(".project.json");
-    write_project_meta(&meta_path, &meta)?;
+        let meta_path = final_folder_path.join(".project.json");
+        let before = fs::read_to_string(&meta_path).map_err(|e| VaultError::io(&meta_path, e))?;
+        write_project_meta(&meta_path, &meta)?;
+        let after = fs::read_to_string(&meta_path).map_err(|e| VaultError::io(&meta_path, e))?;
+        vault.record_step(Step::Write {
+            path: vault.relative(&meta_path)?,
+            before,
+            after,
+        });
+        journal::commit(vault, "Update project");
 
-    Ok(ProjectInfo {
-        id: meta.id,
-        name: meta.name,
-        status: meta.status,
-        created: meta.created,
-        modified: meta.modified,
-        folder_relative_path: relative_from_vault_root(&final_folder_path)?,
+        let folder_relative_path = vault.relative(&final_folder_path)?;
+        let info = ProjectInfo::new(&meta, folder_relative_path.clone());
+        vault.index.upsert_project(folder_relative_path, meta);
+        Ok(info)
     })
 }
@@ -39,6 +39,7 @@ function readStoredNumber(key: string, fallback: number): number {
 
 function App() {
   const init = useHomebaseStore((s) => s.init);
+  const refreshAll = useHomebaseStore((s) => s.refreshAll);
   const isBooting = useHomebaseStore((s) => s.isBooting);
   const lastError = useHomebaseStore((s) => s.lastError);
   const clearError = useHomebaseStore((s) => s.clearError);
@@ -149,6 +150,17 @@ function App() {
     void init();
   }, [init]);
 
+  useEffect(() => {
+    // The vault may have been edited in another app while we were away.
+    const onFocus = () => {
+      if (!useHomebaseStore.getState().isBooting) {
+        void refreshAll({ rescan: true });
+      }
+    };
+    window.addEventListener("focus", onFocus);
+    return () => window.removeEventListener("focus", onFocus);
+  }, [refreshAll]);
+
   useEffect(() => {
     if (!layoutRef.current) return;
     const ro = new ResizeObserver((entries) => {
@@ -1,4 +1,11 @@
-import { useMemo, type CSSProperties } from "react";
+import {
+  useCallback,
+  useEffect,
+  useMemo,
+  useState,
+  type CSSProperties,
+  type ReactNode,
+} from "react";
 import { useDraggable } from "@dnd-kit/core";
 import { CSS } from "@dnd-kit/utilities";
 import { Inbox, FileText, Archive as ArchiveIcon, Folder, Target, Archive, Plus } from "lucide-react";
@@ -8,9 +15,9 @@ import { Badge } from "@/components/ui/badge";
 import { Button } from "@/components/ui/button";
 import { PanelHeader } from "@/components/ui/panel-header";
 import { ScrollArea } from "@/components/ui/scroll-area";
-import { normalizeStringForSearch } from "../lib/markdown";
 import { formatRelativeDate, extractSnippet } from "../lib/dates";
 import type { Note, Project, Collection } from "../lib/types";
+import { formatVaultError, vaultSearch, type SearchHit, type TextRange } from "../lib/vaultApi";
 import { useHomebaseStore } from "../store/useHomebaseStore";
 
 function noteMatchesFolder(note: Note, folderRelativePath: string): boolean {
@@ -26,6 +33,90 @@ function noteMatchesProject(note: Note, project: Project): boolean {
   return note.relativePath.startsWith(prefix);
 }
 
+const SEARCH_PAGE_SIZE = 20;
+const SEARCH_DEBOUNCE_MS = 150;
+
+/** Wraps the highlighted ranges (UTF-16 offsets, as sent by the backend) in `<mark>`. */
+function highlight(text: string, ranges: TextRange[]): ReactNode {
+  if (ranges.length === 0) return text;
+  const parts: ReactNode[] = [];
+  let at = 0;
+  ranges.forEach((range, i) => {
+    if (range.start > at) parts.push(text.slice(at, range.start));
+    parts.push(
+      <mark key={i} className="rounded-sm bg-primary/20 text-foreground">
+        {text.slice(range.start, range.end)}
+      </mark>,
+    );
+    at = range.end;
+  });
+  if (at < text.length) parts.push(text.slice(at));
+  return parts;
+}
+
+/**
+ * Full-text search over the vault, a page at a time. Re-runs when the query
+ * changes or `refreshKey` does, i.e. when the note lists are reloaded; edits
+ * in between keep the results as they were.
+ */
+function useVaultSearch(query: string, enabled: boolean, refreshKey: unknown) {
+  const [hits, setHits] = useState<SearchHit[]>([]);
+  const [total, setTotal] = useState(0);
+  const [nextOffset, setNextOffset] = useState<number | null>(null);
+  const [isLoading, setIsLoading] = useState(false);
+
+  useEffect(() => {
+    if (!enabled || !query.trim()) {
+      setHits([]);
+      setTotal(0);
+      setNextOffset(null);
+      return;
+    }
+    let cancelled = false;
+    const timer = window.setTimeout(() => {
+      setIsLoading(true);
+      vaultSearch({ query, limit: SEARCH_PAGE_SIZE })
+        .then((results) => {
+          if (cancelled) return;
+          setHits(results.hits);
+          setTotal(results.total);
+          setNextOffset(results.nextOffset);
+        })
+        .catch((err) => {
+          if (cancelled) return;
+          setHits([]);
+          setTotal(0);
+          setNextOffset(null);
+          useHomebaseStore.setState({ lastError: formatVaultError(err) });
+        })
+        .finally(() => {
+          if (!cancelled) setIsLoading(false);
+        });
+    }, SEARCH_DEBOUNCE_MS);
+    return () => {
+      cancelled = true;
+      window.clearTimeout(timer);
+    };
+  }, [query, enabled, refreshKey]);
+
+  const loadMore = useCallback(async () => {
+    if (nextOffset === null || isLoading) return;
+    setIsLoading(true);
+    try {
+      const results = await vaultSearch({ query, limit: SEARCH_PAGE_SIZE, offset: nextOffset });
+      setHits((prev) => [...prev, ...results.hits]);
+      setTotal(results.total);
+      setNextOffset(results.nextOffset);
+    } catch (err) {
+      useHomebaseStore.setState({ lastError: formatVaultError(err) });
+    } finally {
+      setIsLoading(false);
+    }
+  }, [query, nextOffset, isLoading]);
+
+  return { hits, total, hasMore: nextOffset !== null, isLoading, loadMore };
+}
+
 function getCollectionDisplay(collection: Collection, projects: Project[]): {
   icon: React.ComponentType<{ className?: string }>;
   label: string;
@@ -62,13 +153,27 @@ export function NoteList({ className }: { className?: string }) {
   const collection = useHomebaseStore((s) => s.collection);
   const searchQuery = useHomebaseStore((s) => s.searchQuery);
   const projects = useHomebaseStore((s) => s.projects);
+  const listVersion = useHomebaseStore((s) => s.listVersion);
+
+  const isSearch = collection.type === "search";
+  const search = useVaultSearch(searchQuery, isSearch, listVersion);
+
+  // Hits in rank order, paired with the loaded note they point at.
+  const searchResults = useMemo(() => {
+    if (!isSearch) return [];
+    const byPath = new Map(notes.map((n) => [n.relativePath, n] as const));
+    return search.hits.flatMap((hit) => {
+      const note = byPath.get(hit.relativePath);
+      return note ? [{ note, hit }] : [];
+    });
+  }, [isSearch, notes, search.hits]);
 
   const visibleNotes = useMemo(() => {
     const nonArchive = notes.filter((n) => n.kind !== "archive");
     const base =
       collection.type === "archive"
         ? notes.filter((n) => n.kind === "archive")
-        : nonArchive.filter((n) => n.kind !== "daily");
+        : nonArchive.filter((n) => n.kind !== "daily" && n.kind !== "periodic");
 
     if (collection.type === "inbox") return base.filter((n) => n.kind === "inbox");
     if (collection.type === "all") return base;
@@ -80,12 +185,16 @@ export function NoteList({ className }: { className?: string }) {
       return base.filter((n) => noteMatchesProject(n, project));
     }
     if (collection.type === "search") {
-      const q = normalizeStringForSearch(searchQuery);
-      if (!q) return nonArchive;
-      return nonArchive.filter((n) => n.searchText.includes(q));
+      return searchQuery.trim() ? searchResults.map((r) => r.note) : nonArchive;
     }
     return base;
-  }, [collection, notes, projects, searchQuery]);
+  }, [collection, notes, projects, searchQuery, searchResults]);
+
+  const hitsByNoteId = useMemo(
+    () => new Map(searchResults.map((r) => [r.note.id, r.hit] as const)),
+    [searchResults],
+  );
+  const noteCount = isSearch && searchQuery.trim() ? search.total : visibleNotes.length;
 
   const showDraft = useMemo(() => {
     if (!draftNote) return false;
@@ -121,7 +230,7 @@ export function NoteList({ className }: { className?: string }) {
             {collectionLabel}
           </div>
           <div className="mt-1 text-xs text-muted-foreground">
-            {visibleNotes.length} {visibleNotes.length === 1 ? "note" : "notes"}
+            {noteCount} {noteCount === 1 ? "note" : "notes"}
           </div>
         </div>
         {collection.type !== "archive" && (
@@ -140,7 +249,9 @@ export function NoteList({ className }: { className?: string }) {
       {/* Notes List */}
       <ScrollArea className="flex-1 min-h-0">
         {visibleNotes.length === 0 && !showDraft ? (
-          <div className="p-4 text-sm text-muted-foreground">No notes.</div>
+          <div className="p-4 text-sm text-muted-foreground">
+            {isSearch && search.isLoading ? "Searching…" : "No notes."}
+          </div>
         ) : (
           <div className="w-full min-w-0 p-2 space-y-1">
             {showDraft && draftNote ? (
@@ -161,12 +272,24 @@ export function NoteList({ className }: { className?: string }) {
               <DraggableNoteCard
                 key={note.id}
                 note={note}
+                hit={hitsByNoteId.get(note.id)}
                 selected={note.id === selectedNoteId}
                 projects={projects}
                 onSelect={(id) => selectNote(id)}
                 onArchive={(id) => void archiveNote(id)}
               />
             ))}
+            {isSearch && search.hasMore ? (
+              <Button
+                variant="ghost"
+                size="sm"
+                className="w-full text-xs text-muted-foreground"
+                disabled={search.isLoading}
+                onClick={() => void search.loadMore()}
+              >
+                {search.isLoading ? "Loading…" : "Show more results"}
+              </Button>
+            ) : null}
           </div>
         )}
       </ScrollArea>
@@ -185,8 +308,8 @@ function NoteCard({
   onClick,
   onArchive,
 }: {
-  title: string;
-  snippet: string;
+  title: ReactNode;
+  snippet: ReactNode;
   date: string;
   projectBadges: { id: string; name: string }[];
   selected: boolean;
@@ -254,12 +377,15 @@ function NoteCard({
 
 function DraggableNoteCard({
   note,
+  hit,
   selected,
   projects,
   onSelect,
   onArchive,
 }: {
   note: Note;
+  /** Present in search results: its highlighted title and snippet are shown. */
+  hit?: SearchHit;
   selected: boolean;
   projects: Project[];
   onSelect: (id: string) => void;
@@ -281,7 +407,10 @@ function DraggableNoteCard({
     })
     .filter((p): p is { id: string; name: string } => p !== null);
 
-  const snippet = extractSnippet(note.body, 80);
+  const snippet = hit
+    ? highlight(hit.snippet, hit.snippetHighlights)
+    : extractSnippet(note.body, 80);
+  const title = hit ? highlight(hit.title, hit.titleHighlights) : note.title || "New note";
   const relativeDate = formatRelativeDate(note.modified);
 
   return (
@@ -296,7 +425,7 @@ function DraggableNoteCard({
       )}
     >
       <NoteCard
-        title={note.title || "New note"}
+        title={title}
         snippet={snippet}
         date={relativeDate}
         projectBadges={projectBadges}
@@ -80,13 +80,6 @@ export function isMeaningfulBody(body: string): boolean {
   return false;
 }
 
-export function normalizeStringForSearch(value: string): string {
-  return (value ?? "")
-    .toLowerCase()
-    .replace(/\s+/g, " ")
-    .trim();
-}
-
 export function nowIso(): string {
   return new Date().toISOString();
 }
@@ -14,8 +14,9 @@ export type Note = {
   topics: string[];
   userPlaced: boolean;
   body: string;
-  searchText: string;
   rawFrontmatter: NoteFrontmatter;
+  /** Hash of the file as last read or written; sent back as `expectedHash` on save. */
+  contentHash: string;
 };
 
 export type DraftNote = {
@@ -1,11 +1,102 @@
-import { invoke } from "@tauri-apps/api/core";
+import { convertFileSrc, invoke } from "@tauri-apps/api/core";
+
+export type LockInfo = {
+  instanceId: string;
+  pid: number;
+  hostname: string | null;
+  acquiredAt: string;
+  heartbeatAt: string;
+};
 
 export type VaultInfo = {
   vaultPath: string;
   version: number;
+  readOnly: boolean;
+  lockHolder: LockInfo | null;
+};
+
+export type VaultError =
+  | { code: "NotFound"; path: string }
+  | { code: "IdNotFound"; kind: string; id: string }
+  | { code: "AlreadyExists"; path: string }
+  | { code: "InvalidPath"; path: string; reason: string }
+  | { code: "OutsideVault"; path: string }
+  | { code: "NotEmpty"; path: string }
+  | { code: "InvalidInput"; field: string; reason: string }
+  | { code: "InvalidData"; path: string; message: string }
+  | { code: "Io"; path: string; message: string }
+  | { code: "Conflict"; path: string; currentContents: string | null }
+  | { code: "ReadOnly"; holder: LockInfo }
+  | { code: "LockContended" }
+  | { code: "UnsupportedVersion"; found: number; supported: number }
+  | { code: "VaultUnavailable"; path: string }
+  | { code: "Internal"; message: string };
+
+export function isVaultError(err: unknown): err is VaultError {
+  return (
+    typeof err === "object" &&
+    err !== null &&
+    typeof (err as { code?: unknown }).code === "string"
+  );
+}
+
+export function formatVaultError(err: unknown): string {
+  if (!isVaultError(err)) return err instanceof Error ? err.message : String(err);
+  switch (err.code) {
+    case "NotFound":
+      return `${err.path} does not exist`;
+    case "IdNotFound":
+      return `No ${err.kind} with id ${err.id}`;
+    case "AlreadyExists":
+      return `${err.path} already exists`;
+    case "InvalidPath":
+      return `Invalid path ${err.path}: ${err.reason}`;
+    case "OutsideVault":
+      return `${err.path} is outside the vault`;
+    case "NotEmpty":
+      return `${err.path} is not empty`;
+    case "InvalidInput":
+      return `Invalid ${err.field}: ${err.reason}`;
+    case "InvalidData":
+      return `${err.path} contains invalid data: ${err.message}`;
+    case "Io":
+      return `I/O error on ${err.path}: ${err.message}`;
+    case "Conflict":
+      return `${err.path} was changed since it was loaded`;
+    case "ReadOnly":
+      return `Vault is read-only: it is open in another Homebase process (pid ${err.holder.pid})`;
+    case "LockContended":
+      return "Vault was locked by another process while opening it";
+    case "UnsupportedVersion":
+      return `Vault format version ${err.found} is newer than the supported version ${err.supported}. Update Homebase to open it.`;
+    case "VaultUnavailable":
+      return `Vault folder ${err.path} is not available`;
+    case "Internal":
+      return err.message;
+  }
+}
+
+export type VaultEntry = {
+  id: string;
+  name: string;
+  path: string;
+  addedAt: string;
+  lastOpenedAt: string | null;
 };
 
-export type VaultNoteKind = "inbox" | "daily" | "folder" | "project" | "archive" | "other";
+export type VaultRegistry = {
+  activeVaultId: string | null;
+  vaults: VaultEntry[];
+};
+
+export type VaultNoteKind =
+  | "inbox"
+  | "daily"
+  | "periodic"
+  | "folder"
+  | "project"
+  | "archive"
+  | "other";
 
 export type VaultNoteEntry = {
   relativePath: string;
@@ -39,21 +130,83 @@ export async function vaultInit(): Promise<VaultInfo> {
   return await invoke("vault_init");
 }
 
+export async function vaultListVaults(): Promise<VaultRegistry> {
+  return await invoke("vault_list_vaults");
+}
+
+export async function vaultAddVault(opts: { path: string; name?: string }): Promise<VaultEntry> {
+  return await invoke("vault_add_vault", opts);
+}
+
+export async function vaultRemoveVault(id: string): Promise<VaultRegistry> {
+  return await invoke("vault_remove_vault", { id });
+}
+
+export async function vaultOpenVault(path: string): Promise<VaultInfo> {
+  return await invoke("vault_open_vault", { path });
+}
+
+export async function vaultSwitchVault(id: string): Promise<VaultInfo> {
+  return await invoke("vault_switch_vault", { id });
+}
+
 export async function vaultListNotes(opts: {
   includeArchived: boolean;
 }): Promise<VaultNoteEntry[]> {
   return await invoke("vault_list_notes", { includeArchived: opts.includeArchived });
 }
 
+/** Picks up notes, folders and projects changed outside the app. */
+export async function vaultRefreshIndex(): Promise<void> {
+  await invoke("vault_refresh_index");
+}
+
 export async function vaultReadNote(relativePath: string): Promise<string> {
   return await invoke("vault_read_note", { relativePath });
 }
 
+export type NoteById = {
+  relativePath: string;
+  contents: string;
+};
+
+export async function vaultResolveNoteId(id: string): Promise<string> {
+  return await invoke("vault_resolve_note_id", { id });
+}
+
+export async function vaultReadNoteById(id: string): Promise<NoteById> {
+  return await invoke("vault_read_note_by_id", { id });
+}
+
+/** Hex SHA-256 of the note contents, matching the hash the backend checks on write. */
+export async function hashNoteContents(contents: string): Promise<string> {
+  const digest = await crypto.subtle.digest("SHA-256", new TextEncoder().encode(contents));
+  return Array.from(new Uint8Array(digest), (b) => b.toString(16).padStart(2, "0")).join("");
+}
+
+export type WriteNoteResult = {
+  /** Differs from the path written to when auto-rename moved the note. */
+  relativePath: string;
+  hash: string;
+};
+
+/**
+ * Fails with `Conflict` if the note no longer hashes to `expectedHash`, and
+ * with `NotFound` if it is gone, unless `expectedHash` is empty.
+ */
 export async function vaultWriteNote(opts: {
   relativePath: string;
   contents: string;
-}): Promise<void> {
-  await invoke("vault_write_note", opts);
+  expectedHash: string;
+}): Promise<WriteNoteResult> {
+  return await invoke("vault_write_note", opts);
+}
+
+export async function vaultForceWriteNote(opts: {
+  relativePath: string;
+  contents: string;
+}): Promise<WriteNoteResult> {
+  return await invoke("vault_force_write_note", opts);
 }
 
 export async function vaultCreateNoteInInbox(): Promise<CreateNoteResult> {
@@ -80,17 +233,34 @@ export async function vaultCreateDailyNote(opts: {
   return await invoke("vault_create_daily_note", { args: opts });
 }
 
-export async function vaultArchiveNote(relativePath: string): Promise<string> {
-  return await invoke("vault_archive_note", { relativePath });
+export type CollisionPolicy = "fail" | "autoSuffix" | "keepBoth";
+
+export async function vaultArchiveNote(
+  relativePath: string,
+  opts?: { onCollision?: CollisionPolicy },
+): Promise<string> {
+  return await invoke("vault_archive_note", { relativePath, onCollision: opts?.onCollision });
+}
+
+export async function vaultUnarchiveNote(
+  relativePath: string,
+  opts?: { onCollision?: CollisionPolicy },
+): Promise<string> {
+  return await invoke("vault_unarchive_note", { relativePath, onCollision: opts?.onCollision });
 }
 
 export async function vaultMoveNote(opts: {
   relativePath: string;
   targetDir: string;
+  onCollision?: CollisionPolicy;
 }): Promise<string> {
   return await invoke("vault_move_note", opts);
 }
 
+export async function vaultRenameNote(relativePath: string): Promise<string> {
+  return await invoke("vault_rename_note", { relativePath });
+}
+
 export async function vaultListFolders(): Promise<string[]> {
   return await invoke("vault_list_folders");
 }
@@ -121,3 +291,351 @@ export async function vaultCreateProject(name: string): Promise<ProjectInfo> {
 export async function vaultUpdateProject(args: UpdateProjectArgs): Promise<ProjectInfo> {
   return await invoke("vault_update_project", { args });
 }
+
+export type CheckIssueKind =
+  | "unreadable"
+  | "missingFrontmatter"
+  | "invalidFrontmatter"
+  | "missingId"
+  | "duplicateNoteId"
+  | "duplicateProjectId"
+  | "invalidProject"
+  | "orphanedTempFile"
+  | "malformedDailyNote"
+  | "malformedPeriodicNote"
+  | "unknownNoteLocation";
+
+export type CheckIssue = {
+  kind: CheckIssueKind;
+  relativePath: string;
+  detail: string;
+  repaired: boolean;
+};
+
+export type CheckReport = {
+  notesScanned: number;
+  issues: CheckIssue[];
+  repairedCount: number;
+  unresolvedCount: number;
+};
+
+export async function vaultCheck(opts?: { repair?: boolean }): Promise<CheckReport> {
+  return await invoke("vault_check", { repair: opts?.repair ?? false });
+}
+
+export type Revision = {
+  id: string;
+  createdAt: string;
+  hash: string;
+  size: number;
+};
+
+export type DiffLine = {
+  kind: "equal" | "insert" | "delete";
+  oldLine: number | null;
+  newLine: number | null;
+  text: string;
+};
+
+export async function vaultListRevisions(noteId: string): Promise<Revision[]> {
+  return await invoke("vault_list_revisions", { noteId });
+}
+
+export async function vaultDiffRevisions(opts: {
+  noteId: string;
+  from: string;
+  to?: string;
+}): Promise<DiffLine[]> {
+  return await invoke("vault_diff_revisions", opts);
+}
+
+export async function vaultRestoreRevision(opts: {
+  noteId: string;
+  revisionId: string;
+}): Promise<NoteById> {
+  return await invoke("vault_restore_revision", opts);
+}
+
+export type VaultSettings = {
+  trashRetentionDays: number;
+  collisionPolicy: CollisionPolicy;
+  autoRenameNotes: boolean;
+  /** Template name in config/templates; pass "" to clear. */
+  dailyTemplate: string | null;
+  weeklyTemplate: string | null;
+  monthlyTemplate: string | null;
+  quarterlyTemplate: string | null;
+  yearlyTemplate: string | null;
+};
+
+export async function vaultGetSettings(): Promise<VaultSettings> {
+  return await invoke("vault_get_settings");
+}
+
+export async function vaultUpdateSettings(args: Partial<VaultSettings>): Promise<VaultSettings> {
+  return await invoke("vault_update_settings", { args });
+}
+
+export type TrashEntry = {
+  id: string;
+  kind: "note" | "folder" | "asset";
+  originalPath: string;
+  deletedAt: string;
+};
+
+export async function vaultTrashNote(relativePath: string): Promise<TrashEntry> {
+  return await invoke("vault_trash_note", { relativePath });
+}
+
+export async function vaultTrashFolder(relativePath: string): Promise<TrashEntry> {
+  return await invoke("vault_trash_folder", { relativePath });
+}
+
+export async function vaultListTrash(): Promise<TrashEntry[]> {
+  return await invoke("vault_list_trash");
+}
+
+export async function vaultRestoreFromTrash(id: string): Promise<string> {
+  return await invoke("vault_restore_from_trash", { id });
+}
+
+export async function vaultEmptyTrash(): Promise<number> {
+  return await invoke("vault_empty_trash");
+}
+
+export type BatchOp =
+  | { op: "move"; relativePath: string; targetDir: string }
+  | { op: "archive"; relativePath: string }
+  | { op: "tag"; relativePath: string; add?: string[]; remove?: string[] }
+  | { op: "delete"; relativePath: string };
+
+export type BatchItemStatus =
+  | "applied"
+  | "invalid"
+  | "skipped"
+  | "failed"
+  | "rolledBack"
+  | "rollbackFailed";
+
+export type BatchItemResult = {
+  status: BatchItemStatus;
+  relativePath: string;
+  error: VaultError | null;
+};
+
+export type BatchResult = {
+  committed: boolean;
+  items: BatchItemResult[];
+};
+
+export async function vaultBatch(ops: BatchOp[]): Promise<BatchResult> {
+  return await invoke("vault_batch", { ops });
+}
+
+export type JournalStep =
+  | { kind: "move"; from: string; to: string }
+  | { kind: "write"; path: string; before: string; after: string }
+  | { kind: "createDir"; path: string }
+  | { kind: "removeDir"; path: string }
+  | { kind: "createFile"; path: string; contents: string }
+  | { kind: "removeFile"; path: string; contents: string }
+  | { kind: "trash"; path: string; entryId: string }
+  | { kind: "untrash"; entryId: string; path: string };
+
+export type JournalEntry = {
+  label: string;
+  at: string;
+  steps: JournalStep[];
+};
+
+export type JournalStatus = {
+  undoLabel: string | null;
+  redoLabel: string | null;
+};
+
+export async function vaultUndo(): Promise<JournalEntry | null> {
+  return await invoke("vault_undo");
+}
+
+export async function vaultRedo(): Promise<JournalEntry | null> {
+  return await invoke("vault_redo");
+}
+
+export async function vaultJournalStatus(): Promise<JournalStatus> {
+  return await invoke("vault_journal_status");
+}
+
+export async function vaultDuplicateNote(relativePath: string): Promise<CreateNoteResult> {
+  return await invoke("vault_duplicate_note", { relativePath });
+}
+
+export type MergeNotesResult = {
+  relativePath: string;
+  contents: string;
+  archived: string[];
+  relinked: string[];
+};
+
+export async function vaultMergeNotes(args: {
+  target: string;
+  sources: string[];
+}): Promise<MergeNotesResult> {
+  return await invoke("vault_merge_notes", { args });
+}
+
+export type SplitNoteResult = {
+  relativePath: string;
+  contents: string;
+  parts: CreateNoteResult[];
+};
+
+export async function vaultSplitNote(opts: {
+  relativePath: string;
+  level: number;
+}): Promise<SplitNoteResult> {
+  return await invoke("vault_split_note", opts);
+}
+
+export async function vaultListTemplates(): Promise<string[]> {
+  return await invoke("vault_list_templates");
+}
+
+export type CreateNoteFromTemplateResult = CreateNoteResult & {
+  /** Where `{{cursor}}` was, as an index into `contents`. */
+  cursor: number | null;
+};
+
+export async function vaultCreateNoteFromTemplate(args: {
+  template: string;
+  title?: string;
+  projectId?: string;
+  targetDir?: string;
+}): Promise<CreateNoteFromTemplateResult> {
+  return await invoke("vault_create_note_from_template", { args });
+}
+
+export type PeriodKind = "day" | "week" | "month" | "quarter" | "year";
+
+export type PeriodInfo = {
+  kind: PeriodKind;
+  /** `2025-03-14`, `2025-W11`, `2025-03`, `2025-Q1` or `2025`. */
+  key: string;
+  start: string;
+  end: string;
+  relativePath: string;
+  exists: boolean;
+};
+
+export async function vaultCreatePeriodicNote(args: {
+  kind: PeriodKind;
+  key: string;
+  contents?: string;
+}): Promise<string> {
+  return await invoke("vault_create_periodic_note", { args });
+}
+
+export async function vaultGetPeriod(kind: PeriodKind, date?: string): Promise<PeriodInfo> {
+  return await invoke("vault_get_period", { kind, date });
+}
+
+export async function vaultPreviousPeriod(kind: PeriodKind, key: string): Promise<PeriodInfo> {
+  return await invoke("vault_previous_period", { kind, key });
+}
+
+export async function vaultNextPeriod(kind: PeriodKind, key: string): Promise<PeriodInfo> {
+  return await invoke("vault_next_period", { kind, key });
+}
+
+export async function vaultListDailyNotesInPeriod(
+  kind: PeriodKind,
+  key: string,
+): Promise<PeriodInfo[]> {
+  return await invoke("vault_list_daily_notes_in_period", { kind, key });
+}
+
+export type AttachmentMeta = {
+  mimeType: string;
+  size: number;
+  originalName: string | null;
+  importedAt: string;
+};
+
+export type ImportAttachmentResult = AttachmentMeta & {
+  relativePath: string;
+  /** Link to insert, rooted at the vault so it survives moving the note. */
+  markdown: string;
+  deduplicated: boolean;
+};
+
+export async function vaultImportAttachment(args: {
+  sourcePath?: string;
+  bytes?: number[];
+  fileName?: string;
+}): Promise<ImportAttachmentResult> {
+  return await invoke("vault_import_attachment", { args });
+}
+
+export type OrphanAsset = {
+  relativePath: string;
+  size: number;
+  modified: string;
+};
+
+export type BrokenAssetLink = {
+  notePath: string;
+  target: string;
+  resolvedPath: string;
+};
+
+export type AssetScan = {
+  orphans: OrphanAsset[];
+  brokenLinks: BrokenAssetLink[];
+  referencedCount: number;
+};
+
+export async function vaultScanAssets(): Promise<AssetScan> {
+  return await invoke("vault_scan_assets");
+}
+
+export type GcAssetsResult = {
+  trashed: TrashEntry[];
+  pending: OrphanAsset[];
+};
+
+export async function vaultGcAssets(graceDays?: number): Promise<GcAssetsResult> {
+  return await invoke("vault_gc_assets", { graceDays });
+}
+
+/** URL of a cached, downscaled preview of an image in `assets/`. */
+export function thumbnailUrl(relativePath: string): string {
+  return convertFileSrc(relativePath, "thumb");
+}
+
+/** Offsets in UTF-16 code units, i.e. usable with `String.prototype.slice`. */
+export type TextRange = { start: number; end: number };
+
+export type SearchHit = {
+  relativePath: string;
+  kind: VaultNoteKind;
+  title: string;
+  titleHighlights: TextRange[];
+  snippet: string;
+  snippetHighlights: TextRange[];
+  score: number;
+};
+
+export type SearchResults = {
+  total: number;
+  hits: SearchHit[];
+  nextOffset: number | null;
+};
+
+/** `"quoted words"` match as a phrase; `word*` matches as a prefix. */
+export async function vaultSearch(args: {
+  query: string;
+  limit?: number;
+  offset?: number;
+  includeArchived?: boolean;
+}): Promise<SearchResults> {
+  return await invoke("vault_search", { args });
+}
@@ -1,6 +1,9 @@
 import { create } from "zustand";
 
 import {
+  formatVaultError,
+  hashNoteContents,
+  isVaultError,
   vaultArchiveNote,
   vaultCreateFolder,
   vaultCreateDailyNote,
@@ -13,7 +16,9 @@ import {
   vaultListProjects,
   vaultMoveNote,
   vaultReadNote,
+  vaultRefreshIndex,
   vaultRenameFolder,
+  vaultResolveNoteId,
   vaultUpdateProject,
   vaultWriteNote,
   type UpdateProjectArgs,
@@ -22,7 +27,6 @@ import {
 import {
   getTitleFromBody,
   isMeaningfulBody,
-  normalizeStringForSearch,
   nowIso,
   parseNoteFile,
   stringifyNoteFile,
@@ -49,13 +53,16 @@ type HomebaseState = {
   draftNote: DraftNote | null;
   folders: string[];
   projects: Project[];
+  /** Bumped each time `refreshAll` reloads the lists. */
+  listVersion: number;
   selectedNoteId: NoteId | null;
   collection: Collection;
   searchQuery: string;
   lastError: string | null;
 
   init: () => Promise<void>;
-  refreshAll: () => Promise<void>;
+  /** Reloads from the vault; `rescan` first picks up changes made outside the app. */
+  refreshAll: (opts?: { rescan?: boolean }) => Promise<void>;
   setCollection: (collection: Collection) => void;
   setSearchQuery: (value: string) => void;
   clearError: () => void;
@@ -63,6 +70,7 @@ type HomebaseState = {
   selectNote: (noteId: NoteId) => void;
   createNote: () => NoteId;
   saveNoteBody: (noteId: NoteId, body: string) => Promise<void>;
+  writeNoteFile: (noteId: NoteId, contents: string) => Promise<void>;
   saveDraftBody: (body: string) => void;
   updateNoteMeta: (noteId: NoteId, patch: UpdateNoteMetaPatch) => Promise<void>;
   archiveNote: (noteId: NoteId) => Promise<void>;
@@ -111,6 +119,7 @@ function coerceIsoString(value: unknown): string | null {
 function noteKindFromRelativePath(relativePath: string): Note["kind"] {
   if (relativePath.startsWith("notes/inbox/")) return "inbox";
   if (relativePath.startsWith("notes/daily/")) return "daily";
+  if (/^notes\/(weekly|monthly|quarterly|yearly)\/[^/]+$/.test(relativePath)) return "periodic";
   if (relativePath.startsWith("notes/archive/")) return "archive";
   if (relativePath.startsWith("notes/projects/")) return "project";
   if (relativePath.startsWith("notes/folders/")) return "folder";
@@ -125,7 +134,25 @@ function noteKindFromTargetDir(targetDir: string): DraftNote["kind"] {
   return "other";
 }
 
-function buildNoteFromFile(entry: VaultNoteEntry, fileMarkdown: string): Note | null {
+// Saves to one note run one at a time, so each carries the hash the previous one produced.
+const pendingNoteWrites = new Map<NoteId, Promise<unknown>>();
+
+function queueNoteWrite<T>(noteId: NoteId, write: () => Promise<T>): Promise<T> {
+  const previous = pendingNoteWrites.get(noteId) ?? Promise.resolve();
+  const next = previous.catch(() => undefined).then(write);
+  pendingNoteWrites.set(noteId, next);
+  const cleanup = () => {
+    if (pendingNoteWrites.get(noteId) === next) pendingNoteWrites.delete(noteId);
+  };
+  next.then(cleanup, cleanup);
+  return next;
+}
+
+function buildNoteFromFile(
+  entry: VaultNoteEntry,
+  fileMarkdown: string,
+  contentHash: string,
+): Note | null {
   const parsed = parseNoteFile(fileMarkdown);
   const id = typeof parsed.frontmatter.id === "string" ? parsed.frontmatter.id : null;
   if (!id) return null;
@@ -138,8 +165,6 @@ function buildNoteFromFile(entry: VaultNoteEntry, fileMarkdown: string): Note |
   const userPlaced = parsed.frontmatter.user_placed === true;
   const title = getTitleFromBody(parsed.body);
 
-  const searchText = normalizeStringForSearch([title, parsed.body].join("\n"));
-
   return {
     id,
     relativePath: entry.relativePath,
@@ -151,8 +176,8 @@ function buildNoteFromFile(entry: VaultNoteEntry, fileMarkdown: string): Note |
     topics,
     userPlaced,
     body: parsed.body,
-    searchText,
     rawFrontmatter: parsed.frontmatter,
+    contentHash,
   };
 }
 
@@ -164,6 +189,7 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
   draftNote: null,
   folders: [],
   projects: [],
+  listVersion: 0,
   selectedNoteId: null,
   collection: { type: "daily" },
   searchQuery: "",
@@ -176,14 +202,17 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
       set({ vaultPath: info.vaultPath, vaultVersion: info.version });
       await get().refreshAll();
     } catch (err) {
-      set({ lastError: err instanceof Error ? err.message : String(err) });
+      set({ lastError: formatVaultError(err) });
     } finally {
       set({ isBooting: false });
     }
   },
 
-  refreshAll: async () => {
+  refreshAll: async (opts) => {
     try {
+      if (opts?.rescan) {
+        await vaultRefreshIndex();
+      }
       const [entries, folders, projects] = await Promise.all([
         vaultListNotes({ includeArchived: true }),
         vaultListFolders(),
@@ -193,18 +222,19 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
       const notesWithContent = await Promise.all(
         entries.map(async (entry) => {
           const markdown = await vaultReadNote(entry.relativePath);
-          return buildNoteFromFile(entry, markdown);
+          return buildNoteFromFile(entry, markdown, await hashNoteContents(markdown));
         }),
       );
 
       const notes = notesWithContent.filter((n): n is Note => n !== null);
       notes.sort((a, b) => b.modified.localeCompare(a.modified));
 
-      set({
+      set((s) => ({
         notes,
         folders,
         projects,
-      });
+        listVersion: s.listVersion + 1,
+      }));
 
       const currentSelected = get().selectedNoteId;
       const draft = get().draftNote;
@@ -215,7 +245,7 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
         set({ selectedNoteId: notes[0]?.id ?? null });
       }
     } catch (err) {
-      set({ lastError: err instanceof Error ? err.message : String(err) });
+      set({ lastError: formatVaultError(err) });
     }
   },
 
@@ -294,7 +324,6 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
 
     const modified = nowIso();
     const title = getTitleFromBody(body);
-    const searchText = normalizeStringForSearch([title, body].join("\n"));
 
     const frontmatter = {
       ...(note.rawFrontmatter ?? {}),
@@ -310,18 +339,50 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
     set((state) => ({
       notes: state.notes.map((n) =>
         n.id === noteId
-          ? { ...n, body, modified, title, searchText, rawFrontmatter: frontmatter }
+          ? { ...n, body, modified, title, rawFrontmatter: frontmatter }
           : n,
       ),
     }));
 
     try {
-      await vaultWriteNote({ relativePath: note.relativePath, contents: full });
+      await get().writeNoteFile(noteId, full);
     } catch (err) {
-      set({ lastError: err instanceof Error ? err.message : String(err) });
+      set({ lastError: formatVaultError(err) });
     }
   },
 
+  writeNoteFile: async (noteId, contents) => {
+    await queueNoteWrite(noteId, async () => {
+      const note = get().notes.find((n) => n.id === noteId);
+      if (!note) return;
+      let relativePath = note.relativePath;
+      let expectedHash = note.contentHash;
+      const write = () => vaultWriteNote({ relativePath, contents, expectedHash });
+      let result;
+      try {
+        result = await write();
+      } catch (err) {
+        if (!isVaultError(err) || err.code !== "NotFound") throw err;
+        // Moved or deleted outside the app: follow the id, or failing that
+        // put the note back where it was rather than lose the edit.
+        try {
+          relativePath = await vaultResolveNoteId(noteId);
+        } catch (resolveErr) {
+          if (!isVaultError(resolveErr) || resolveErr.code !== "IdNotFound") throw resolveErr;
+          expectedHash = "";
+        }
+        result = await write();
+      }
+      set((state) => ({
+        notes: state.notes.map((n) =>
+          n.id === noteId
+            ? { ...n, relativePath: result.relativePath, contentHash: result.hash }
+            : n,
+        ),
+      }));
+    });
+  },
+
   saveDraftBody: (body) => {
     const draft = get().draftNote;
     if (!draft) return;
@@ -389,12 +450,16 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
           user_placed: draft.userPlaced,
         } as Record<string, unknown>;
         const finalContents = stringifyNoteFile(finalFrontmatter, finalBody);
-        await vaultWriteNote({ relativePath, contents: finalContents });
+        const written = await vaultWriteNote({
+          relativePath,
+          contents: finalContents,
+          expectedHash: await hashNoteContents(initialContents),
+        });
 
         const note: Note = {
           id: draft.id,
-          relativePath,
-          kind: noteKindFromRelativePath(relativePath),
+          relativePath: written.relativePath,
+          kind: noteKindFromRelativePath(written.relativePath),
           title: finalTitle,
           created: draft.created,
           modified: finalModified,
@@ -402,8 +467,8 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
           topics: draft.topics,
           userPlaced: draft.userPlaced,
           body: finalBody,
-          searchText: normalizeStringForSearch([finalTitle, finalBody].join("\n")),
           rawFrontmatter: finalFrontmatter,
+          contentHash: written.hash,
         };
 
         set((state) => ({
@@ -412,7 +477,7 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
         }));
       } catch (err) {
         set((state) => ({
-          lastError: err instanceof Error ? err.message : String(err),
+          lastError: formatVaultError(err),
           draftNote: state.draftNote ? { ...state.draftNote, isPersisting: false } : state.draftNote,
         }));
       }
@@ -448,9 +513,9 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
     }));
 
     try {
-      await vaultWriteNote({ relativePath: note.relativePath, contents: full });
+      await get().writeNoteFile(noteId, full);
     } catch (err) {
-      set({ lastError: err instanceof Error ? err.message : String(err) });
+      set({ lastError: formatVaultError(err) });
     }
   },
 
@@ -460,10 +525,18 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
 
     try {
       const newRelativePath = await vaultArchiveNote(note.relativePath);
+      // Archiving stamps archived_at/archived_from into the frontmatter.
+      const markdown = await vaultReadNote(newRelativePath);
+      const contentHash = await hashNoteContents(markdown);
+      const archived = buildNoteFromFile(
+        { relativePath: newRelativePath, kind: "archive", mtimeMs: Date.now(), size: markdown.length },
+        markdown,
+        contentHash,
+      );
       set((state) => ({
         notes: state.notes.map((n) =>
           n.id === noteId
-            ? { ...n, relativePath: newRelativePath, kind: "archive" }
+            ? (archived ?? { ...n, relativePath: newRelativePath, kind: "archive", contentHash })
             : n,
         ),
         selectedNoteId:
@@ -472,7 +545,7 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
             : state.selectedNoteId,
       }));
     } catch (err) {
-      set({ lastError: err instanceof Error ? err.message : String(err) });
+      set({ lastError: formatVaultError(err) });
     }
   },
 
@@ -501,7 +574,7 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
         await get().updateNoteMeta(noteId, { userPlaced: shouldUserPlace });
       }
     } catch (err) {
-      set({ lastError: err instanceof Error ? err.message : String(err) });
+      set({ lastError: formatVaultError(err) });
     }
   },
 
@@ -520,13 +593,14 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
         const note = buildNoteFromFile(
           { relativePath, kind: "daily", mtimeMs: Date.now(), size: markdown.length },
           markdown,
+          await hashNoteContents(markdown),
         );
         if (!note) return;
         set((state) => ({
           notes: [note, ...state.notes].sort((a, b) => b.modified.localeCompare(a.modified)),
         }));
       } catch (err) {
-        set({ lastError: err instanceof Error ? err.message : String(err) });
+        set({ lastError: formatVaultError(err) });
       }
       return;
     }
@@ -617,15 +691,15 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
         topics: [],
         userPlaced: false,
         body,
-        searchText: normalizeStringForSearch([getTitleFromBody(body), body].join("\n")),
         rawFrontmatter: frontmatter,
+        contentHash: await hashNoteContents(contents),
       };
 
       set((state) => ({
         notes: [note, ...state.notes].sort((a, b) => b.modified.localeCompare(a.modified)),
       }));
     } catch (err) {
-      set({ lastError: err instanceof Error ? err.message : String(err) });
+      set({ lastError: formatVaultError(err) });
     }
   },
 
@@ -635,7 +709,7 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
       const folders = await vaultListFolders();
       set({ folders });
     } catch (err) {
-      set({ lastError: err instanceof Error ? err.message : String(err) });
+      set({ lastError: formatVaultError(err) });
     }
   },
 
@@ -644,7 +718,7 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
       await vaultRenameFolder({ fromRelativePath, toName });
       await get().refreshAll();
     } catch (err) {
-      set({ lastError: err instanceof Error ? err.message : String(err) });
+      set({ lastError: formatVaultError(err) });
     }
   },
 
@@ -653,7 +727,7 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
       await vaultDeleteFolder(relativePath);
       await get().refreshAll();
     } catch (err) {
-      set({ lastError: err instanceof Error ? err.message : String(err) });
+      set({ lastError: formatVaultError(err) });
     }
   },
 
@@ -674,7 +748,7 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
         ].sort((a, b) => a.name.toLowerCase().localeCompare(b.name.toLowerCase())),
       }));
     } catch (err) {
-      set({ lastError: err instanceof Error ? err.message : String(err) });
+      set({ lastError: formatVaultError(err) });
     }
   },
 
@@ -697,7 +771,7 @@ export const useHomebaseStore = create<HomebaseState>((set, get) => ({
       }));
       await get().refreshAll();
     } catch (err) {
-      set({ lastError: err instanceof Error ? err.message : String(err) });
+      set({ lastError: formatVaultError(err) });
     }
   },
 }));